@@ -0,0 +1,908 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{File, Permissions},
+    io::{BufReader, Cursor, Read, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+use crate::repository::{read_snapshot, Repository, SNAPSHOT_EXTENSION};
+
+/// The backups of a single game, stored as `<id>.backup` files in one directory,
+/// or as `<id>.snapshot` files for backups in the [`Repository`].
+/// The id is the UTC time the backup was made, so sorting by id sorts by age.
+#[derive(Debug, Clone)]
+pub struct BackupStore {
+    dir: PathBuf,
+}
+
+/// A backup in the store
+#[derive(Debug, Clone)]
+pub struct StoredBackup {
+    pub id: String,
+    pub path: PathBuf,
+    pub created: OffsetDateTime,
+    pub size: u64,
+}
+
+/// Name of the entry at the start of a backup archive that describes the backup
+pub const MANIFEST: &str = "manifest.json";
+
+/// Folder in a backup archive for files from the game dir, everything else is relative to the compat dir
+pub const GAME_DIR: &str = "game";
+
+/// Folder in a backup archive for registry keys picked out of the hive with the same name,
+/// restoring them merges them into the hive in the prefix
+pub const REGISTRY_DIR: &str = "registry";
+
+/// Describes what is in a backup and where it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Name of the game, if it was added with one
+    pub name: Option<String>,
+    pub save_name: String,
+    /// When the backup was made, in seconds since the unix epoch
+    pub created: i64,
+    /// Proton version the prefix was last used with
+    pub proton: Option<String>,
+    /// Machine the backup was made on
+    pub host: String,
+    /// Files in the backup relative to the compat dir
+    pub files: BTreeMap<PathBuf, ManifestFile>,
+    /// Files that were deleted since the baseline, restoring the backup deletes them too
+    #[serde(default)]
+    pub deleted: Vec<PathBuf>,
+    /// Where the files in the `game` folder of the archive came from, if there are any
+    #[serde(default)]
+    pub game_dir: Option<PathBuf>,
+    /// How the files are laid out in the archive
+    #[serde(default)]
+    pub layout: Layout,
+    /// Registry keys in the `registry` folder, like `HKEY_CURRENT_USER/Software/Vendor/Game`
+    #[serde(default)]
+    pub registry: Vec<String>,
+}
+
+/// How a backup is packed, both can be restored and are told apart by their contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "commandline", derive(clap::ValueEnum))]
+pub enum BackupFormat {
+    /// A zstd compressed tar archive, which keeps everything about the files
+    TarZst,
+    /// A zip archive, which can be opened anywhere including Windows
+    Zip,
+}
+
+impl BackupFormat {
+    /// The format a file name asks for, `None` if its extension doesn't say
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") || name.ends_with(".backup")
+        {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+
+    /// The format of an existing backup, from the magic bytes at its start
+    pub fn detect(path: &Path) -> std::io::Result<Self> {
+        let mut magic = [0u8; 4];
+        let mut f = File::open(path)?;
+        let n = f.read(&mut magic)?;
+        Ok(if n == 4 && magic == *b"PK\x03\x04" {
+            Self::Zip
+        } else {
+            Self::TarZst
+        })
+    }
+}
+
+/// Where the profile of the wine user is, relative to the compat dir
+const PROFILE_DIR: &str = "pfx/drive_c/users/steamuser";
+
+/// How the files of a backup are laid out in the archive.
+/// Whatever the layout, the manifest lists them relative to the compat dir.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// Relative to the compat dir, like `pfx/drive_c/users/steamuser/AppData/Roaming/...`
+    #[default]
+    Compat,
+    /// Like on Windows, so the saves can be dropped onto a Windows install:
+    /// the user profile at the top like `AppData/Roaming/...`, the rest of drive C in `C`,
+    /// and what only makes sense to proton, like the registry hives, in `proton`
+    Windows,
+}
+
+impl Layout {
+    /// Where a file, relative to the compat dir, goes in the archive
+    pub fn archive_path(&self, path: &Path) -> PathBuf {
+        match self {
+            Layout::Compat => path.to_path_buf(),
+            Layout::Windows => {
+                // Profile folders named like the other folders of the layout would come back as those,
+                // they stay in their place on drive C instead
+                let rest = path.strip_prefix(PROFILE_DIR).ok().filter(|rest| {
+                    !["C", "proton", GAME_DIR]
+                        .iter()
+                        .any(|d| rest.starts_with(d))
+                });
+                if let Some(rest) = rest {
+                    rest.to_path_buf()
+                } else if let Ok(rest) = path.strip_prefix("pfx/drive_c") {
+                    Path::new("C").join(rest)
+                } else if path.starts_with(GAME_DIR) {
+                    path.to_path_buf()
+                } else {
+                    Path::new("proton").join(path)
+                }
+            }
+        }
+    }
+
+    /// Where a file in the archive goes, relative to the compat dir
+    pub fn compat_path(&self, path: &Path) -> PathBuf {
+        match self {
+            Layout::Compat => path.to_path_buf(),
+            Layout::Windows => {
+                if let Ok(rest) = path.strip_prefix("C") {
+                    Path::new("pfx/drive_c").join(rest)
+                } else if let Ok(rest) = path.strip_prefix("proton") {
+                    rest.to_path_buf()
+                } else if path.starts_with(GAME_DIR) {
+                    path.to_path_buf()
+                } else {
+                    Path::new(PROFILE_DIR).join(path)
+                }
+            }
+        }
+    }
+}
+
+/// Writes a backup archive in either format, the manifest has to be added first
+pub enum BackupWriter {
+    TarZst(tar::Builder<zstd::Encoder<'static, File>>),
+    Zip(zip::ZipWriter<File>),
+}
+
+impl BackupWriter {
+    pub fn create(path: &Path, format: BackupFormat) -> std::io::Result<Self> {
+        let f = File::create(path)?;
+        Ok(match format {
+            BackupFormat::TarZst => Self::TarZst(tar::Builder::new(zstd::Encoder::new(f, 3)?)),
+            BackupFormat::Zip => Self::Zip(zip::ZipWriter::new(f)),
+        })
+    }
+
+    pub fn add_manifest(&mut self, manifest: &BackupManifest) -> std::io::Result<()> {
+        let json = manifest.to_json();
+        match self {
+            Self::TarZst(t) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(json.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(manifest.created as u64);
+                header.set_cksum();
+                t.append_data(&mut header, MANIFEST, json.as_bytes())
+            }
+            Self::Zip(z) => {
+                let mtime = manifest.created().map(SystemTime::from);
+                z.start_file(MANIFEST, zip_options(0o644, mtime, json.len() as u64))?;
+                z.write_all(json.as_bytes())
+            }
+        }
+    }
+
+    /// Add the file at `source` as `name`
+    pub fn add_file(&mut self, source: &Path, name: &Path) -> std::io::Result<()> {
+        match self {
+            Self::TarZst(t) => t.append_path_with_name(source, name),
+            Self::Zip(z) => {
+                let metadata = source.metadata()?;
+                let options = zip_options(
+                    metadata.permissions().mode(),
+                    metadata.modified().ok(),
+                    metadata.len(),
+                );
+                z.start_file(name.to_string_lossy(), options)?;
+                std::io::copy(&mut File::open(source)?, z)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Add a file with the given contents as `name`
+    pub fn add_data(
+        &mut self,
+        name: &Path,
+        data: &[u8],
+        mtime: OffsetDateTime,
+    ) -> std::io::Result<()> {
+        match self {
+            Self::TarZst(t) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(mtime.unix_timestamp() as u64);
+                header.set_cksum();
+                t.append_data(&mut header, name, data)
+            }
+            Self::Zip(z) => {
+                z.start_file(
+                    name.to_string_lossy(),
+                    zip_options(0o644, Some(mtime.into()), data.len() as u64),
+                )?;
+                z.write_all(data)
+            }
+        }
+    }
+
+    pub fn finish(self) -> std::io::Result<()> {
+        match self {
+            Self::TarZst(t) => {
+                t.into_inner()?.finish()?;
+            }
+            Self::Zip(mut z) => {
+                z.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What an entry of a backup archive is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    /// A symlink to the path, relative to where the link is
+    Symlink(PathBuf),
+    /// A hard link to the path, relative to the root of the archive
+    HardLink(PathBuf),
+    Other,
+}
+
+/// An entry of a backup archive, whichever format it is in
+pub struct BackupEntry<'a> {
+    /// Path in the archive, as it is in there
+    pub path: PathBuf,
+    pub kind: EntryKind,
+    /// Modification time in seconds since the unix epoch
+    pub mtime: u64,
+    inner: EntryInner<'a>,
+}
+
+enum EntryInner<'a> {
+    Tar(tar::Entry<'a, zstd::Decoder<'static, BufReader<File>>>),
+    Zip(zip::read::ZipFile<'a>),
+    /// A file of a snapshot, which is a blob of the repository
+    Blob(zstd::Decoder<'static, BufReader<File>>),
+    /// The manifest of a snapshot
+    Snapshot(Cursor<Vec<u8>>),
+}
+
+impl BackupEntry<'_> {
+    /// Write the entry to `dest`, whose parent has to exist
+    pub fn unpack(&mut self, dest: &Path) -> std::io::Result<()> {
+        match &mut self.inner {
+            EntryInner::Tar(entry) => entry.unpack(dest).map(|_| ()),
+            EntryInner::Zip(file) => {
+                if file.is_dir() {
+                    return std::fs::create_dir_all(dest);
+                }
+                let mode = file.unix_mode().unwrap_or(0o644);
+                write_file(file, dest, mode, self.mtime)
+            }
+            EntryInner::Blob(r) => write_file(r, dest, 0o644, self.mtime),
+            EntryInner::Snapshot(r) => write_file(r, dest, 0o644, self.mtime),
+        }
+    }
+}
+
+fn write_file(r: &mut impl Read, dest: &Path, mode: u32, mtime: u64) -> std::io::Result<()> {
+    let mut f = File::create(dest)?;
+    std::io::copy(r, &mut f)?;
+    f.set_permissions(Permissions::from_mode(mode & 0o777))?;
+    f.set_modified(UNIX_EPOCH + Duration::from_secs(mtime))
+}
+
+impl Read for BackupEntry<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.inner {
+            EntryInner::Tar(entry) => entry.read(buf),
+            EntryInner::Zip(file) => file.read(buf),
+            EntryInner::Blob(r) => r.read(buf),
+            EntryInner::Snapshot(r) => r.read(buf),
+        }
+    }
+}
+
+/// Go through the entries of a backup in order, the manifest is the first one if there is one.
+/// The files of a snapshot are read from `repository`.
+pub fn read_backup<E: From<std::io::Error>>(
+    path: &Path,
+    repository: &Repository,
+    mut visit: impl FnMut(BackupEntry<'_>) -> Result<(), E>,
+) -> Result<(), E> {
+    if Repository::is_snapshot(path) {
+        let content = std::fs::read(path)?;
+        let manifest = read_snapshot(path)?;
+        // Blobs don't know when their file was changed, so files are as old as the snapshot
+        let mtime = manifest.created.try_into().unwrap_or_default();
+        visit(BackupEntry {
+            path: PathBuf::from(MANIFEST),
+            kind: EntryKind::File,
+            mtime,
+            inner: EntryInner::Snapshot(Cursor::new(content)),
+        })?;
+        for (file, info) in &manifest.files {
+            visit(BackupEntry {
+                path: manifest.layout.archive_path(file),
+                kind: EntryKind::File,
+                mtime,
+                inner: EntryInner::Blob(repository.open_blob(&info.hash)?),
+            })?;
+        }
+        return Ok(());
+    }
+    match BackupFormat::detect(path)? {
+        BackupFormat::TarZst => {
+            let d = zstd::Decoder::new(File::open(path)?)?;
+            let mut archive = tar::Archive::new(d);
+            for entry in archive.entries()? {
+                let entry = entry?;
+                let header = entry.header();
+                let entry_type = header.entry_type();
+                let link = || -> std::io::Result<PathBuf> {
+                    Ok(entry.link_name()?.unwrap_or_default().to_path_buf())
+                };
+                let kind = if entry_type.is_file() {
+                    EntryKind::File
+                } else if entry_type.is_dir() {
+                    EntryKind::Dir
+                } else if entry_type.is_symlink() {
+                    EntryKind::Symlink(link()?)
+                } else if entry_type.is_hard_link() {
+                    EntryKind::HardLink(link()?)
+                } else {
+                    EntryKind::Other
+                };
+                visit(BackupEntry {
+                    path: entry.path()?.to_path_buf(),
+                    kind,
+                    mtime: header.mtime()?,
+                    inner: EntryInner::Tar(entry),
+                })?;
+            }
+        }
+        BackupFormat::Zip => {
+            let mut archive =
+                zip::ZipArchive::new(File::open(path)?).map_err(std::io::Error::from)?;
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i).map_err(std::io::Error::from)?;
+                // Zip has no entry types, links and such are only told apart by the unix mode
+                let kind = match file.unix_mode().map(|mode| mode & S_IFMT) {
+                    _ if file.is_dir() => EntryKind::Dir,
+                    None | Some(0) | Some(S_IFREG) => EntryKind::File,
+                    Some(S_IFDIR) => EntryKind::Dir,
+                    Some(S_IFLNK) => {
+                        let mut target = String::new();
+                        file.read_to_string(&mut target)?;
+                        EntryKind::Symlink(PathBuf::from(target))
+                    }
+                    Some(_) => EntryKind::Other,
+                };
+                visit(BackupEntry {
+                    path: PathBuf::from(file.name().trim_end_matches('/')),
+                    kind,
+                    mtime: from_zip_time(file.last_modified()),
+                    inner: EntryInner::Zip(file),
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Options for a zip entry of `size` bytes, which needs zip64 from 4 GiB on
+fn zip_options(mode: u32, mtime: Option<SystemTime>, size: u64) -> zip::write::FileOptions {
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(mode)
+        .large_file(size >= u32::MAX as u64);
+    match mtime.and_then(to_zip_time) {
+        Some(mtime) => options.last_modified_time(mtime),
+        None => options,
+    }
+}
+
+/// Zip files store the local time without an offset, like Windows does
+fn to_zip_time(time: SystemTime) -> Option<zip::DateTime> {
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    let t = OffsetDateTime::from(time).to_offset(offset);
+    zip::DateTime::from_date_and_time(
+        t.year().try_into().ok()?,
+        t.month() as u8,
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second(),
+    )
+    .ok()
+}
+
+fn from_zip_time(time: zip::DateTime) -> u64 {
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    let date = Month::try_from(time.month())
+        .ok()
+        .and_then(|month| Date::from_calendar_date(time.year() as i32, month, time.day()).ok());
+    let time = Time::from_hms(time.hour(), time.minute(), time.second()).ok();
+    match (date, time) {
+        (Some(date), Some(time)) => PrimitiveDateTime::new(date, time)
+            .assume_offset(offset)
+            .unix_timestamp()
+            .try_into()
+            .unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub size: u64,
+    /// sha256 of the contents as a hex string
+    pub hash: String,
+}
+
+/// Name of this machine, so backups and exported prefixes tell where they were made
+pub fn host_name() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .unwrap_or_default()
+}
+
+impl BackupManifest {
+    pub fn new(save_name: &str, created: OffsetDateTime) -> Self {
+        Self {
+            name: None,
+            save_name: save_name.to_string(),
+            created: created.unix_timestamp(),
+            proton: None,
+            host: host_name(),
+            files: BTreeMap::new(),
+            deleted: Vec::new(),
+            game_dir: None,
+            layout: Layout::Compat,
+            registry: Vec::new(),
+        }
+    }
+
+    /// Read the manifest from the start of a backup, backups made before manifests existed have none
+    pub fn read(backup: &Path) -> std::io::Result<Option<Self>> {
+        if Repository::is_snapshot(backup) {
+            return read_snapshot(backup).map(Some);
+        }
+        let content = match BackupFormat::detect(backup)? {
+            BackupFormat::TarZst => {
+                let d = zstd::Decoder::new(File::open(backup)?)?;
+                let mut archive = tar::Archive::new(d);
+                let Some(entry) = archive.entries()?.next() else {
+                    return Ok(None);
+                };
+                let mut entry = entry?;
+                if entry.path()?.as_ref() != Path::new(MANIFEST) {
+                    return Ok(None);
+                }
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                content
+            }
+            BackupFormat::Zip => {
+                let mut archive =
+                    zip::ZipArchive::new(File::open(backup)?).map_err(std::io::Error::from)?;
+                let Ok(mut file) = archive.by_name(MANIFEST) else {
+                    return Ok(None);
+                };
+                let mut content = String::new();
+                file.read_to_string(&mut content)?;
+                content
+            }
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn created(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::from_unix_timestamp(self.created).ok()
+    }
+}
+
+/// Which backups to keep, anything not matched by any of the rules is removed.
+/// If no rule is set all backups are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Retention {
+    /// Keep this many of the newest backups
+    #[cfg_attr(feature = "commandline", clap(long, value_parser = at_least_one()))]
+    pub keep_last: Option<usize>,
+    /// Keep the newest backup of each of the last this many days that have backups
+    #[cfg_attr(feature = "commandline", clap(long, value_parser = at_least_one()))]
+    pub keep_daily: Option<usize>,
+    /// Keep the newest backup of each of the last this many weeks that have backups
+    #[cfg_attr(feature = "commandline", clap(long, value_parser = at_least_one()))]
+    pub keep_weekly: Option<usize>,
+}
+
+/// Keeping none of something would remove every backup, including the one that was just made
+#[cfg(feature = "commandline")]
+fn at_least_one() -> clap::builder::RangedU64ValueParser<usize> {
+    clap::builder::RangedU64ValueParser::new().range(1..)
+}
+
+impl Retention {
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none() && self.keep_daily.is_none() && self.keep_weekly.is_none()
+    }
+
+    /// Take over the rules that are set in `other`
+    pub fn merge(&mut self, other: &Retention) {
+        self.keep_last = other.keep_last.or(self.keep_last);
+        self.keep_daily = other.keep_daily.or(self.keep_daily);
+        self.keep_weekly = other.keep_weekly.or(self.keep_weekly);
+    }
+
+    /// Ids of the backups to keep, the backups have to be sorted oldest first.
+    /// The newest one is always kept, even if a config written by hand keeps none.
+    pub fn kept(&self, backups: &[StoredBackup]) -> HashSet<String> {
+        if self.is_empty() {
+            return backups.iter().map(|b| b.id.clone()).collect();
+        }
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let mut kept = HashSet::new();
+        let mut days = Vec::new();
+        let mut weeks = Vec::new();
+        for (i, backup) in backups.iter().rev().enumerate() {
+            let local = backup.created.to_offset(offset);
+            let day = local.date();
+            let (year, week, _) = day.to_iso_week_date();
+            let week = (year, week);
+            if i == 0 || self.keep_last.is_some_and(|n| i < n) {
+                kept.insert(backup.id.clone());
+            }
+            if !days.contains(&day) {
+                days.push(day);
+                if self.keep_daily.is_some_and(|n| days.len() <= n) {
+                    kept.insert(backup.id.clone());
+                }
+            }
+            if !weeks.contains(&week) {
+                weeks.push(week);
+                if self.keep_weekly.is_some_and(|n| weeks.len() <= n) {
+                    kept.insert(backup.id.clone());
+                }
+            }
+        }
+        kept
+    }
+}
+
+impl BackupStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// All backups in the store, oldest first
+    pub fn list(&self) -> std::io::Result<Vec<StoredBackup>> {
+        let mut backups = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|e| e == "backup" || e == SNAPSHOT_EXTENSION)
+            {
+                if let Some(backup) = StoredBackup::from_path(path) {
+                    backups.push(backup);
+                }
+            }
+        }
+        backups.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(backups)
+    }
+
+    /// Find a backup by id, `latest` or a unique start of an id
+    pub fn get(&self, id: &str) -> std::io::Result<Option<StoredBackup>> {
+        let backups = self.list()?;
+        if id == "latest" {
+            return Ok(backups.into_iter().last());
+        }
+        let mut matches = backups.into_iter().filter(|b| b.id.starts_with(id));
+        Ok(match (matches.next(), matches.next()) {
+            (Some(backup), None) => Some(backup),
+            _ => None,
+        })
+    }
+
+    /// Path for a new backup made at the given time
+    pub fn new_backup_path(&self, created: OffsetDateTime) -> PathBuf {
+        self.dir.join(format!("{}.backup", self.new_id(created)))
+    }
+
+    /// Path for a new snapshot in the repository made at the given time
+    pub fn new_snapshot_path(&self, created: OffsetDateTime) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", self.new_id(created), SNAPSHOT_EXTENSION))
+    }
+
+    /// An id no backup or snapshot has yet
+    fn new_id(&self, created: OffsetDateTime) -> String {
+        let base = format_id(created);
+        let mut id = base.clone();
+        let mut n = 1;
+        while ["backup", SNAPSHOT_EXTENSION]
+            .iter()
+            .any(|ext| self.dir.join(format!("{}.{}", id, ext)).exists())
+        {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+        id
+    }
+
+    /// Remove the backups the retention rules don't keep, returning the removed ones
+    pub fn apply_retention(&self, retention: &Retention) -> std::io::Result<Vec<StoredBackup>> {
+        let backups = self.list()?;
+        let kept = retention.kept(&backups);
+        let mut removed = Vec::new();
+        for backup in backups {
+            if !kept.contains(&backup.id) {
+                std::fs::remove_file(&backup.path)?;
+                removed.push(backup);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+impl StoredBackup {
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let id = path.file_stem()?.to_str()?.to_string();
+        let created = parse_id(&id)?;
+        let size = path.metadata().ok()?.len();
+        Some(Self {
+            id,
+            path,
+            created,
+            size,
+        })
+    }
+
+    /// When the backup was made in local time, for showing to the user
+    pub fn local_time(&self) -> String {
+        format_local(self.created)
+    }
+
+    /// Whether the backup is a snapshot in the repository instead of an archive
+    pub fn is_snapshot(&self) -> bool {
+        Repository::is_snapshot(&self.path)
+    }
+}
+
+/// Format a time in the local timezone like `2023-04-01 13:37:00`
+pub fn format_local(time: OffsetDateTime) -> String {
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    let t = time.to_offset(offset);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        t.year(),
+        t.month() as u8,
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second()
+    )
+}
+
+/// Ids look like `20230401-113700`, in UTC
+fn format_id(time: OffsetDateTime) -> String {
+    let t = time.to_offset(UtcOffset::UTC);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        t.year(),
+        t.month() as u8,
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second()
+    )
+}
+
+fn parse_id(id: &str) -> Option<OffsetDateTime> {
+    let num = |range: std::ops::Range<usize>| id.get(range)?.parse::<u32>().ok();
+    if id.as_bytes().get(8) != Some(&b'-') {
+        return None;
+    }
+    let date = Date::from_calendar_date(
+        num(0..4)? as i32,
+        Month::try_from(num(4..6)? as u8).ok()?,
+        num(6..8)? as u8,
+    )
+    .ok()?;
+    let time = Time::from_hms(num(9..11)? as u8, num(11..13)? as u8, num(13..15)? as u8).ok()?;
+    Some(PrimitiveDateTime::new(date, time).assume_utc())
+}
+
+/// Size in a human readable unit
+pub fn format_size(size: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, units[unit])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_round_trip() {
+        let paths = [
+            "pfx/drive_c/users/steamuser/AppData/Roaming/Game/save.dat",
+            "pfx/drive_c/users/steamuser/Documents/My Games/Game/1.sav",
+            "pfx/drive_c/Games/Game/config.ini",
+            "pfx/user.reg",
+            "registry/user.reg",
+            "game/saves/1.sav",
+            // Profile folders named like the folders of the Windows layout
+            "pfx/drive_c/users/steamuser/C/save.dat",
+            "pfx/drive_c/users/steamuser/proton/save.dat",
+            "pfx/drive_c/users/steamuser/game/save.dat",
+        ];
+        for layout in [Layout::Compat, Layout::Windows] {
+            for path in paths {
+                let path = Path::new(path);
+                let archived = layout.archive_path(path);
+                assert_eq!(layout.compat_path(&archived), path, "{:?}", layout);
+            }
+        }
+    }
+
+    #[test]
+    fn windows_layout_puts_the_profile_at_the_top() {
+        let archived = |path: &str| Layout::Windows.archive_path(Path::new(path));
+        assert_eq!(
+            archived("pfx/drive_c/users/steamuser/AppData/Roaming/Game/save.dat"),
+            Path::new("AppData/Roaming/Game/save.dat")
+        );
+        assert_eq!(
+            archived("pfx/drive_c/Games/config.ini"),
+            Path::new("C/Games/config.ini")
+        );
+        assert_eq!(archived("pfx/user.reg"), Path::new("proton/pfx/user.reg"));
+        assert_eq!(archived("game/1.sav"), Path::new("game/1.sav"));
+    }
+
+    /// Backups a day or a week apart, all made close to noon UTC so they are on the same days in every timezone
+    fn backups() -> Vec<StoredBackup> {
+        [
+            "20230405-113000",
+            "20230406-113000",
+            "20230412-113000",
+            "20230419-113000",
+            "20230419-115000",
+            "20230420-113000",
+        ]
+        .into_iter()
+        .map(|id| StoredBackup {
+            id: id.to_string(),
+            path: PathBuf::from(format!("{}.backup", id)),
+            created: parse_id(id).unwrap(),
+            size: 0,
+        })
+        .collect()
+    }
+
+    fn kept(retention: Retention) -> Vec<String> {
+        let mut kept: Vec<String> = retention.kept(&backups()).into_iter().collect();
+        kept.sort();
+        kept
+    }
+
+    #[test]
+    fn ids_round_trip() {
+        for backup in backups() {
+            assert_eq!(format_id(backup.created), backup.id);
+        }
+        assert_eq!(parse_id("20230405113000"), None);
+        assert_eq!(parse_id("20231305-113000"), None);
+        assert_eq!(parse_id("2023"), None);
+    }
+
+    #[test]
+    fn retention_keeps_everything_without_rules() {
+        assert_eq!(kept(Retention::default()).len(), 6);
+    }
+
+    #[test]
+    fn retention_keeps_the_newest() {
+        let retention = Retention {
+            keep_last: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(kept(retention), ["20230419-115000", "20230420-113000"]);
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_of_each_day() {
+        let retention = Retention {
+            keep_daily: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            kept(retention),
+            ["20230412-113000", "20230419-115000", "20230420-113000"]
+        );
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_of_each_week() {
+        let retention = Retention {
+            keep_weekly: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            kept(retention),
+            ["20230406-113000", "20230412-113000", "20230420-113000"]
+        );
+    }
+
+    #[test]
+    fn retention_keeps_what_any_rule_keeps() {
+        let mut retention = Retention {
+            keep_last: Some(1),
+            keep_weekly: Some(1),
+            ..Default::default()
+        };
+        retention.merge(&Retention {
+            keep_daily: Some(2),
+            keep_weekly: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(retention.keep_last, Some(1));
+        assert_eq!(
+            kept(retention),
+            ["20230412-113000", "20230419-115000", "20230420-113000"]
+        );
+        let none = Retention {
+            keep_last: Some(0),
+            keep_daily: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(kept(none), ["20230420-113000"]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hash::hash_file,
+    registry::{HIVES, HIVE_FILES},
+};
+
+/// The files a prefix had at some point, like right after it was created.
+/// Comparing the prefix against it tells which files the game made or changed,
+/// which is what backups are interested in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Files relative to the prefix
+    pub files: BTreeMap<PathBuf, FileState>,
+    /// Contents of the registry hives by file name, so changes to single keys can be shown.
+    /// They are saved next to the baseline instead of in it, see [`Baseline::hive`]
+    #[serde(skip)]
+    registry: BTreeMap<String, String>,
+}
+
+/// What a file looked like when the baseline was taken
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileState {
+    pub size: u64,
+    /// Modification time in nanoseconds since the unix epoch
+    pub mtime: u64,
+    /// sha256 of the contents as a hex string
+    pub hash: String,
+}
+
+impl FileState {
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let metadata = path.metadata()?;
+        Ok(Self {
+            size: metadata.len(),
+            mtime: mtime(&metadata),
+            hash: hash_file(path)?,
+        })
+    }
+}
+
+/// Files that differ between the prefix and its baseline, relative to the prefix
+#[derive(Debug, Clone, Default)]
+pub struct Changes {
+    pub added: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+
+    /// Files that were added or modified, so their contents have to be backed up
+    pub fn changed(&self) -> impl Iterator<Item = &PathBuf> {
+        self.added.iter().chain(self.modified.iter())
+    }
+}
+
+impl Baseline {
+    /// Where the baseline of a compat dir is stored
+    pub fn path(compat_dir: &Path) -> PathBuf {
+        compat_dir.join("baseline.json")
+    }
+
+    pub fn exists(compat_dir: &Path) -> bool {
+        Self::path(compat_dir).is_file()
+    }
+
+    /// Where a registry hive of the baseline is stored, like `baseline.user.reg`
+    pub fn hive_path(compat_dir: &Path, hive: &str) -> PathBuf {
+        compat_dir.join(format!("baseline.{}", hive))
+    }
+
+    /// A registry hive as it was when the baseline was taken.
+    /// `None` if the prefix didn't have it, or the baseline is from before hives were recorded.
+    pub fn hive(compat_dir: &Path, hive: &str) -> std::io::Result<Option<String>> {
+        match std::fs::read_to_string(Self::hive_path(compat_dir, hive)) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Record every file in the prefix of the compat dir
+    pub fn scan(compat_dir: &Path) -> std::io::Result<Self> {
+        let prefix = compat_dir.join("pfx");
+        let mut files = BTreeMap::new();
+        for entry in walkdir::WalkDir::new(&prefix) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let path = entry.path().strip_prefix(&prefix).unwrap().to_path_buf();
+                files.insert(path, FileState::read(entry.path())?);
+            }
+        }
+        let mut registry = BTreeMap::new();
+        for (hive, _) in HIVES {
+            if let Ok(content) = std::fs::read_to_string(prefix.join(hive)) {
+                registry.insert(hive.to_string(), content);
+            }
+        }
+        Ok(Self { files, registry })
+    }
+
+    pub fn load(compat_dir: &Path) -> std::io::Result<Self> {
+        let f = File::open(Self::path(compat_dir))?;
+        serde_json::from_reader(f)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, compat_dir: &Path) -> std::io::Result<()> {
+        for (hive, _) in HIVES {
+            let path = Self::hive_path(compat_dir, hive);
+            match self.registry.get(hive) {
+                Some(content) => std::fs::write(&path, content)?,
+                None if path.is_file() => std::fs::remove_file(&path)?,
+                None => {}
+            }
+        }
+        let f = File::create(Self::path(compat_dir))?;
+        serde_json::to_writer(f, self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Whether the file, relative to the prefix, was there when the baseline was taken
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Compare the prefix of the compat dir against the baseline.
+    /// Files are only hashed when their size or mtime changed,
+    /// and the parts of the prefix that wine itself manages are skipped.
+    pub fn changes(&self, compat_dir: &Path) -> std::io::Result<Changes> {
+        let prefix = compat_dir.join("pfx");
+        let mut changes = Changes::default();
+        let mut seen = Vec::new();
+        let walker = walkdir::WalkDir::new(&prefix)
+            .into_iter()
+            .filter_entry(|e| !is_ignored(e.path().strip_prefix(&prefix).unwrap()));
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path().strip_prefix(&prefix).unwrap().to_path_buf();
+            match self.files.get(&path) {
+                None => changes.added.push(path.clone()),
+                Some(old) => {
+                    let metadata = entry.metadata()?;
+                    let touched = metadata.len() != old.size || mtime(&metadata) != old.mtime;
+                    if touched && hash_file(entry.path())? != old.hash {
+                        changes.modified.push(path.clone());
+                    }
+                }
+            }
+            seen.push(path);
+        }
+        seen.sort();
+        changes.deleted = self
+            .files
+            .keys()
+            .filter(|p| !is_ignored(p) && seen.binary_search(p).is_err())
+            .cloned()
+            .collect();
+        Ok(changes)
+    }
+}
+
+/// Parts of the prefix that belong to wine and never hold anything worth backing up.
+/// The hives are among them since wine rewrites them on almost every start,
+/// the keys of a game in them are backed up on their own and `prefix diff` compares them key by key.
+fn is_ignored(path: &Path) -> bool {
+    path.starts_with("drive_c/windows")
+        || path.ends_with("Temp")
+        || HIVE_FILES.iter().any(|hive| path == Path::new(hive))
+}
+
+fn mtime(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
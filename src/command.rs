@@ -1,32 +1,44 @@
-
 use crate::{paths::Paths, steam::SteamData};
 
+mod prompt;
 mod runnable;
 pub use runnable::*;
 
 pub mod backup;
+pub mod backups;
 pub mod desktop_entry;
+pub mod game;
 pub mod info;
+pub mod inspect;
 pub mod install;
+pub mod install_game;
 pub mod move_compat;
+pub mod prefix;
 pub mod restore;
 pub mod run;
+pub mod stop;
 pub mod uninstall;
+pub mod winetricks;
 
 #[cfg_attr(feature = "commandline", derive(clap::Subcommand))]
 pub enum ProtonCommand {
     /// Run a game with proton
+    ///
+    /// Exits with the exit code of the game, 126 if it couldn't be started and 125 if proton-launch itself failed
     Run(run::Run),
 
     /// Create a compat folder for a game
     MoveCompat(move_compat::MoveCompat),
 
-    /// Get newly added files in the compat folder
+    /// Back up the files the game added or changed in its compat folder
     Backup(backup::Backup),
 
     /// Restore files from a backup
     Restore(restore::Restore),
 
+    /// Manage the backups of a game
+    Backups(backups::Backups),
+
     /// Install a proton version, will do nothing if it's already installed
     /// (or well that's what Steam seems to do)
     Install(install::Install),
@@ -40,6 +52,24 @@ pub enum ProtonCommand {
 
     /// Create a desktop entry for a game
     DesktopEntry(desktop_entry::MakeDE),
+
+    /// Stop a running game, killing it if it doesn't exit in time
+    Stop(stop::Stop),
+
+    /// Manage the wine prefix of a game
+    Prefix(prefix::Prefix),
+
+    /// Apply winetricks verbs to the prefix of a game
+    Winetricks(winetricks::Winetricks),
+
+    /// Manage added games and their settings
+    Game(game::Game),
+
+    /// Run a game installer in a new prefix and add the game it installed
+    InstallGame(install_game::InstallGame),
+
+    /// Show what can be read from an exe, like its architecture, engine and version info
+    Inspect(inspect::Inspect),
 }
 
 impl Runnable for ProtonCommand {
@@ -49,10 +79,17 @@ impl Runnable for ProtonCommand {
             ProtonCommand::MoveCompat(m) => m.run(paths, steam_data),
             ProtonCommand::Backup(b) => b.run(paths, steam_data),
             ProtonCommand::Restore(r) => r.run(paths, steam_data),
+            ProtonCommand::Backups(b) => b.run(paths, steam_data),
             ProtonCommand::Install(i) => i.run(paths, steam_data),
             ProtonCommand::Uninstall(u) => u.run(paths, steam_data),
             ProtonCommand::Info(i) => i.run(paths, steam_data),
             ProtonCommand::DesktopEntry(d) => d.run(paths, steam_data),
+            ProtonCommand::Stop(s) => s.run(paths, steam_data),
+            ProtonCommand::Prefix(p) => p.run(paths, steam_data),
+            ProtonCommand::Winetricks(w) => w.run(paths, steam_data),
+            ProtonCommand::Game(g) => g.run(paths, steam_data),
+            ProtonCommand::InstallGame(i) => i.run(paths, steam_data),
+            ProtonCommand::Inspect(i) => i.run(paths, steam_data),
         }
     }
 }
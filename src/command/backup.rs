@@ -1,64 +1,419 @@
 use std::{
-    collections::HashSet,
-    fs::File,
+    collections::BTreeMap,
     path::{Path, PathBuf},
 };
 
-use crate::{paths::Paths, steam::SteamData};
+use time::OffsetDateTime;
 
-use super::{Runnable, RunnableResult};
+use crate::{
+    backups::{
+        BackupFormat, BackupManifest, BackupStore, BackupWriter, Layout, ManifestFile,
+        StoredBackup, GAME_DIR, REGISTRY_DIR,
+    },
+    baseline::{Baseline, FileState},
+    game::{save_name_for, GameConfig},
+    hash::hash_bytes,
+    lock::PrefixLock,
+    ludusavi::Manifest,
+    paths::Paths,
+    proton::prefix_version,
+    registry::{hive_of, RegistryFile, HIVES},
+    steam::SteamData,
+};
+
+use super::{Runnable, RunnableError, RunnableResult};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "commandline", derive(clap::Args))]
 pub struct Backup {
-    /// Path to the game exe
+    /// Path to the game exe, or the name of an added game
     exe: PathBuf,
 
     /// Optional save name to use
-    /// If not specified, the game exe without the extension will be used
+    /// If not specified, it is picked like `game add` does, from the product name of the exe or its file name
     #[cfg_attr(feature = "commandline", clap(short, long))]
     save_name: Option<String>,
+
+    /// Also record which files were deleted since the baseline, so restoring the backup deletes them too
+    #[cfg_attr(feature = "commandline", clap(long))]
+    deletions: bool,
+
+    /// Back up the saves the ludusavi manifest knows about instead of what changed since the baseline.
+    /// This is the default for games linked to the manifest with `game link --ludusavi`
+    #[cfg_attr(feature = "commandline", clap(long))]
+    ludusavi: bool,
+
+    /// The ludusavi manifest to use, if not specified the one ludusavi itself downloaded is used
+    #[cfg_attr(feature = "commandline", clap(long))]
+    ludusavi_manifest: Option<PathBuf>,
+
+    /// Also back up this registry key and the keys below it, like `HKEY_CURRENT_USER/Software/Vendor/Game`.
+    /// Can be given multiple times, keys listed in the config of the game are always backed up
+    #[cfg_attr(feature = "commandline", clap(long))]
+    registry_key: Vec<String>,
+
+    /// Write the backup to this file instead of the backups of the game.
+    /// A `.zip` extension makes it a zip unless `--format` says otherwise
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    output: Option<PathBuf>,
+
+    /// Format of the backup, if not specified it follows the extension of `--output` or is tar.zst
+    #[cfg_attr(feature = "commandline", clap(long))]
+    format: Option<BackupFormat>,
+
+    /// Lay the files out like on Windows, like `AppData/Roaming/...`, so they can be dropped onto a Windows install.
+    /// Restoring such a backup puts them back into the prefix
+    #[cfg_attr(feature = "commandline", clap(long))]
+    windows_layout: bool,
+
+    /// Store the backup in the deduplicated repository, so files that didn't change take no extra space.
+    /// This is the default for games set up with `backups repository`
+    #[cfg_attr(
+        feature = "commandline",
+        clap(long, conflicts_with_all = ["output", "format", "windows_layout"])
+    )]
+    repository: bool,
+}
+
+/// What making a backup did
+#[derive(Debug)]
+pub enum BackupOutcome {
+    /// A backup was made, and these older ones were removed by the retention rules
+    Created {
+        path: PathBuf,
+        removed: Vec<StoredBackup>,
+    },
+    /// The saves are the same as in the latest backup, so no new one was made
+    Unchanged,
+}
+
+impl Backup {
+    /// Back up an added game the way its config says, like after it exited
+    pub fn game(save_name: &str) -> Self {
+        Self {
+            exe: PathBuf::from(save_name),
+            save_name: None,
+            deletions: false,
+            ludusavi: false,
+            ludusavi_manifest: None,
+            registry_key: Vec::new(),
+            output: None,
+            format: None,
+            windows_layout: false,
+            repository: false,
+        }
+    }
+
+    /// Make the backup, or skip it if `skip_unchanged` is set and it would contain the same as the latest one.
+    /// A caller that already holds the lock on the compat dir, like `run` after the game exited, can pass it
+    /// so nothing else can get in between.
+    pub fn create(
+        &self,
+        paths: &Paths,
+        skip_unchanged: bool,
+        held: Option<&PrefixLock>,
+    ) -> RunnableResult<BackupOutcome> {
+        let game_name = self.exe.to_string_lossy();
+        let is_game = GameConfig::is_added(paths, &self.exe);
+        let default_name;
+        let (save_name, exe) = match self.save_name.as_deref() {
+            Some(save_name) => (save_name, (!is_game).then_some(self.exe.as_path())),
+            None if is_game => (game_name.as_ref(), None),
+            None => {
+                default_name = save_name_for(paths, &self.exe);
+                (default_name.as_str(), Some(self.exe.as_path()))
+            }
+        };
+        let compat_dir = paths.compat_dir(save_name, exe)?;
+        let _lock = match held {
+            Some(lock) if lock.compat_dir() == compat_dir => None,
+            _ => Some(paths.lock_compat_dir(&compat_dir)?),
+        };
+        let config = GameConfig::load(paths, save_name)?;
+        let created = OffsetDateTime::now_utc();
+        let mut manifest = BackupManifest::new(save_name, created);
+        manifest.name = config.name.clone();
+        manifest.proton = prefix_version(&compat_dir).or(config.proton.map(|p| p.to_string()));
+
+        // Where each file in the archive comes from
+        let mut sources = BTreeMap::new();
+        // The hives themselves change on every start, so only these keys are picked out of them
+        let mut keys: Vec<String> = config.registry_keys.clone();
+        keys.extend(self.registry_key.iter().cloned());
+        if let Some(key) = keys.iter().find(|k| hive_of(k).is_none()) {
+            return Err(RunnableError::NotInHive(key.clone()));
+        }
+        if self.ludusavi || self.ludusavi_manifest.is_some() || config.ludusavi.is_some() {
+            let path = self
+                .ludusavi_manifest
+                .clone()
+                .or_else(Manifest::default_path)
+                .ok_or(RunnableError::NoLudusaviManifest)?;
+            println!("Reading the ludusavi manifest from {}", path.display());
+            let ludusavi = Manifest::load(&path)?;
+            let name = config.ludusavi.as_deref().or(config.name.as_deref());
+            let (name, game) = ludusavi
+                .find(name, config.steam_app_id)
+                .or_else(|| ludusavi.find(Some(save_name), None))
+                .ok_or_else(|| RunnableError::NotInLudusavi(save_name.to_string()))?;
+            let base_dir = exe.or(config.exe.as_deref()).map(|exe| game.base_dir(exe));
+            let saves = game.resolve(&compat_dir.join("pfx"), base_dir.as_deref());
+            println!(
+                "Backing up the saves of {} from the ludusavi manifest",
+                name
+            );
+
+            for file in saves.files {
+                if let Ok(path) = file.strip_prefix(&compat_dir) {
+                    sources.insert(path.to_path_buf(), file.clone());
+                } else if let Some(path) = base_dir.as_ref().and_then(|b| file.strip_prefix(b).ok())
+                {
+                    sources.insert(Path::new(GAME_DIR).join(path), file.clone());
+                }
+            }
+            if sources.is_empty() {
+                println!("Warning: none of the save locations of {} exist", name);
+            }
+            if sources.keys().any(|p| p.starts_with(GAME_DIR)) {
+                manifest.game_dir = base_dir;
+            }
+            // Keys in hives that aren't in the prefix, like HKEY_CLASSES_ROOT, are left out
+            keys.extend(saves.registry.into_iter().filter(|k| hive_of(k).is_some()));
+        } else {
+            if !Baseline::exists(&compat_dir) {
+                return Err(RunnableError::NoBaseline(save_name.to_string()));
+            }
+            let changes = Baseline::load(&compat_dir)?.changes(&compat_dir)?;
+            println!(
+                "{} added, {} modified and {} deleted files since the baseline",
+                changes.added.len(),
+                changes.modified.len(),
+                changes.deleted.len()
+            );
+            let prefix = Path::new("pfx");
+            for path in changes.changed() {
+                sources.insert(prefix.join(path), compat_dir.join(prefix).join(path));
+            }
+            if self.deletions {
+                manifest.deleted = changes.deleted.iter().map(|p| prefix.join(p)).collect();
+            }
+        }
+        keys.sort();
+        keys.dedup();
+        // Registry keys picked out of the hives, as `.reg` files by where they go in the archive
+        let mut registry = BTreeMap::new();
+        for (hive, _) in HIVES {
+            let keys: Vec<&String> = keys
+                .iter()
+                .filter(|k| hive_of(k).is_some_and(|(h, _)| h == hive))
+                .collect();
+            let path = compat_dir.join("pfx").join(hive);
+            if keys.is_empty() || !path.is_file() {
+                continue;
+            }
+            let in_hive: Vec<String> = keys.iter().filter_map(|k| Some(hive_of(k)?.1)).collect();
+            let extracted = RegistryFile::load(&path)?.extract(&in_hive);
+            if extracted.keys.is_empty() {
+                println!(
+                    "Warning: none of the registry keys of {} are in {}",
+                    save_name, hive
+                );
+                continue;
+            }
+            println!(
+                "Backing up {} registry keys from {}",
+                extracted.keys.len(),
+                hive
+            );
+            registry.insert(
+                Path::new(REGISTRY_DIR).join(hive),
+                extracted.to_string().into_bytes(),
+            );
+            manifest.registry.extend(keys.into_iter().cloned());
+        }
+        for (path, source) in &sources {
+            let state = FileState::read(source)?;
+            manifest.files.insert(
+                path.clone(),
+                ManifestFile {
+                    size: state.size,
+                    hash: state.hash,
+                },
+            );
+        }
+        for (path, data) in &registry {
+            manifest.files.insert(
+                path.clone(),
+                ManifestFile {
+                    size: data.len() as u64,
+                    hash: hash_bytes(data),
+                },
+            );
+        }
+
+        let store = BackupStore::new(paths.backups_dir(save_name));
+        if skip_unchanged {
+            let latest = match store.list()?.last() {
+                Some(latest) => BackupManifest::read(&latest.path)?,
+                None => None,
+            };
+            let unchanged = match latest {
+                Some(latest) => {
+                    latest.files == manifest.files && latest.deleted == manifest.deleted
+                }
+                None => manifest.files.is_empty() && manifest.deleted.is_empty(),
+            };
+            if unchanged {
+                return Ok(BackupOutcome::Unchanged);
+            }
+        }
+        let into_repository = self.output.is_none()
+            && self.format.is_none()
+            && !self.windows_layout
+            && (self.repository || config.repository);
+        if into_repository {
+            let repository = paths.repository();
+            let _repository_lock = repository.lock()?;
+            let mut stored = 0;
+            for (path, source) in &sources {
+                let file = manifest.files.get_mut(path).unwrap();
+                if !repository.has_blob(&file.hash) {
+                    // The file could have changed since it was hashed, what was stored is what counts
+                    file.hash = repository.add_blob(source)?;
+                    file.size = source.metadata()?.len();
+                    stored += 1;
+                }
+            }
+            for (path, data) in &registry {
+                if !repository.has_blob(&manifest.files[path].hash) {
+                    repository.add_blob_from(data.as_slice())?;
+                    stored += 1;
+                }
+            }
+            println!(
+                "Stored {} of {} files in the repository, the rest were already in it",
+                stored,
+                manifest.files.len()
+            );
+            let snapshot_path = store.new_snapshot_path(created);
+            std::fs::write(&snapshot_path, manifest.to_json())?;
+            let removed = store.apply_retention(&config.retention)?;
+            return Ok(BackupOutcome::Created {
+                path: snapshot_path,
+                removed,
+            });
+        }
+
+        if self.windows_layout {
+            manifest.layout = Layout::Windows;
+        }
+        let backup_path = match &self.output {
+            Some(output) => output.clone(),
+            None => store.new_backup_path(created),
+        };
+        let format = self
+            .format
+            .or_else(|| {
+                self.output
+                    .as_deref()
+                    .and_then(BackupFormat::from_extension)
+            })
+            .unwrap_or(BackupFormat::TarZst);
+        let mut writer = BackupWriter::create(&backup_path, format)?;
+        writer.add_manifest(&manifest)?;
+        for (path, source) in &sources {
+            writer.add_file(source, &manifest.layout.archive_path(path))?;
+        }
+        for (path, data) in &registry {
+            writer.add_data(&manifest.layout.archive_path(path), data, created)?;
+        }
+        writer.finish()?;
+
+        // Retention is about the backups in the store, a backup written somewhere else isn't one of them
+        let removed = match self.output {
+            Some(_) => Vec::new(),
+            None => store.apply_retention(&config.retention)?,
+        };
+        Ok(BackupOutcome::Created {
+            path: backup_path,
+            removed,
+        })
+    }
 }
 
 impl Runnable for Backup {
     fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
-        let save_name = self
-            .save_name
-            .as_deref()
-            .unwrap_or_else(|| self.exe.file_stem().unwrap().to_str().unwrap());
-        let global_compat_dir = paths.compat_dir(save_name);
-        let r = find_new_files(&global_compat_dir).unwrap();
-        let f = File::create(format!("{}.backup", save_name)).unwrap();
-        let w = zstd::Encoder::new(f, 3).unwrap().auto_finish();
-        let mut t = tar::Builder::new(w);
-        for f in r {
-            let path = f.strip_prefix(&global_compat_dir).unwrap();
-            t.append_path_with_name(&f, path).unwrap();
+        if let BackupOutcome::Created { path, removed } = self.create(paths, false, None)? {
+            println!("Created backup {}", path.display());
+            for removed in removed {
+                println!(
+                    "Removed backup {} from {}",
+                    removed.id,
+                    removed.local_time()
+                );
+            }
         }
-        t.finish().unwrap();
         Ok(())
     }
 }
 
-fn find_new_files(compat_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
-    let mut v = Vec::new();
-    let tracked_files = compat_dir.join("tracked_files");
-    let tracked_files = std::fs::read_to_string(tracked_files)?;
-    let tracked_files: HashSet<&str> = tracked_files.lines().collect();
-    let prefix = compat_dir.join("pfx");
-
-    for entry in walkdir::WalkDir::new(&prefix) {
-        let entry = entry?;
-        let path = entry.path();
-        if entry.file_type().is_file() {
-            let path = path.strip_prefix(&prefix).unwrap();
-            if !tracked_files.contains(path.to_str().unwrap())
-                && path.to_string_lossy().contains("users")
-            {
-                v.push(entry.path().to_path_buf());
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HIVE: &str = r#"WINE REGISTRY Version 2
+
+[Software\\Vendor\\Game] 1681234567
+"Volume"=dword:00000005
+
+[Software\\Wine] 1681234567
+"Version"="win10"
+"#;
+
+    #[test]
+    fn skips_sessions_that_only_touched_the_hives() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::in_dir(dir.path(), false);
+        GameConfig {
+            registry_keys: vec!["HKEY_CURRENT_USER/Software/Vendor/Game".to_string()],
+            ..Default::default()
         }
-    }
+        .save(&paths, "game")
+        .unwrap();
+        let compat_dir = paths.compat_dir("game", None).unwrap();
+        let prefix = compat_dir.join("pfx");
+        std::fs::create_dir_all(prefix.join("drive_c/users/steamuser")).unwrap();
+        std::fs::write(prefix.join("user.reg"), HIVE).unwrap();
+        std::fs::write(prefix.join("system.reg"), "WINE REGISTRY Version 2\n").unwrap();
+        Baseline::scan(&compat_dir)
+            .unwrap()
+            .save(&compat_dir)
+            .unwrap();
 
-    Ok(v)
+        std::fs::write(prefix.join("drive_c/users/steamuser/save.dat"), b"1").unwrap();
+        let backup = Backup::game("game");
+        let Ok(BackupOutcome::Created { path, .. }) = backup.create(&paths, true, None) else {
+            panic!("the new save should have been backed up");
+        };
+        let manifest = BackupManifest::read(&path).unwrap().unwrap();
+        assert!(manifest.files.contains_key(Path::new("registry/user.reg")));
+        assert!(!manifest.files.contains_key(Path::new("pfx/user.reg")));
+
+        // Like wine does on every start, without touching the keys of the game
+        let rewritten = HIVE.replace("win10", "win11");
+        std::fs::write(prefix.join("user.reg"), rewritten).unwrap();
+        std::fs::write(prefix.join("system.reg"), "WINE REGISTRY Version 2\n\n").unwrap();
+        std::fs::write(prefix.join("userdef.reg"), "WINE REGISTRY Version 2\n").unwrap();
+        assert!(matches!(
+            backup.create(&paths, true, None),
+            Ok(BackupOutcome::Unchanged)
+        ));
+
+        let changed = HIVE.replace("00000005", "00000006");
+        std::fs::write(prefix.join("user.reg"), changed).unwrap();
+        assert!(matches!(
+            backup.create(&paths, true, None),
+            Ok(BackupOutcome::Created { .. })
+        ));
+    }
 }
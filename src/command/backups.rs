@@ -0,0 +1,69 @@
+use crate::{
+    backups::{BackupStore, StoredBackup},
+    paths::Paths,
+    steam::SteamData,
+};
+
+use super::{Runnable, RunnableError, RunnableResult};
+
+pub mod auto;
+pub mod check;
+pub mod list;
+pub mod prune;
+pub mod repository;
+pub mod retention;
+pub mod show;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Backups {
+    #[cfg_attr(feature = "commandline", command(subcommand))]
+    command: BackupsCommand,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Subcommand))]
+pub enum BackupsCommand {
+    /// List the backups of a game, oldest first
+    List(list::List),
+
+    /// Set which backups of a game to keep and remove the ones that don't match
+    Retention(retention::SetRetention),
+
+    /// Show where a backup came from and which files are in it
+    Show(show::Show),
+
+    /// Back up a game every time it exits after `proton-launch run`
+    Auto(auto::SetAuto),
+
+    /// Store the backups of a game in the deduplicated repository, which only stores each file once
+    Repository(repository::UseRepository),
+
+    /// Remove the files from the repository that no backup needs anymore
+    Prune(prune::Prune),
+
+    /// Verify that the files in the repository are intact and that no backup is missing any
+    Check(check::Check),
+}
+
+impl Runnable for Backups {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        match &self.command {
+            BackupsCommand::List(l) => l.run(paths, steam_data),
+            BackupsCommand::Retention(r) => r.run(paths, steam_data),
+            BackupsCommand::Show(s) => s.run(paths, steam_data),
+            BackupsCommand::Auto(a) => a.run(paths, steam_data),
+            BackupsCommand::Repository(r) => r.run(paths, steam_data),
+            BackupsCommand::Prune(p) => p.run(paths, steam_data),
+            BackupsCommand::Check(c) => c.run(paths, steam_data),
+        }
+    }
+}
+
+/// Find a backup of a game by id, or the latest one if no id is given
+pub fn find_backup(paths: &Paths, game: &str, id: Option<&str>) -> RunnableResult<StoredBackup> {
+    let id = id.unwrap_or("latest");
+    BackupStore::new(paths.backups_dir(game))
+        .get(id)?
+        .ok_or_else(|| RunnableError::UnknownBackup(game.to_string(), id.to_string()))
+}
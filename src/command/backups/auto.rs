@@ -0,0 +1,37 @@
+use crate::{
+    command::{Runnable, RunnableError, RunnableResult},
+    game::GameConfig,
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct SetAuto {
+    /// Save name of the game
+    game: String,
+
+    /// Stop backing up the game automatically
+    #[cfg_attr(feature = "commandline", clap(long))]
+    off: bool,
+}
+
+impl Runnable for SetAuto {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        if !GameConfig::exists(paths, &self.game) {
+            return Err(RunnableError::UnknownGame(self.game.clone()));
+        }
+        let mut config = GameConfig::load(paths, &self.game)?;
+        config.auto_backup = !self.off;
+        config.save(paths, &self.game)?;
+        if config.auto_backup {
+            println!(
+                "{} will be backed up every time it exits, unless nothing changed since the last backup",
+                self.game
+            );
+        } else {
+            println!("{} will no longer be backed up automatically", self.game);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{
+    command::{Runnable, RunnableError, RunnableResult},
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Check {}
+
+impl Runnable for Check {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let snapshots = paths.snapshots()?;
+        let report = paths.repository().check(&snapshots)?;
+        for hash in &report.damaged {
+            println!("Damaged blob {}", hash);
+        }
+        for (snapshot, path, hash) in &report.missing {
+            println!(
+                "Missing blob {} for {} in {}",
+                hash,
+                path.display(),
+                snapshot.display()
+            );
+        }
+        println!(
+            "Checked {} blobs and {} snapshots",
+            report.blobs,
+            snapshots.len()
+        );
+        if report.is_ok() {
+            println!("Everything is fine");
+            Ok(())
+        } else {
+            Err(RunnableError::RepositoryDamaged(
+                report.damaged.len() + report.missing.len(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{
+    backups::{format_size, BackupStore},
+    command::{Runnable, RunnableResult},
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct List {
+    /// Save name of the game
+    game: String,
+}
+
+impl Runnable for List {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let backups = BackupStore::new(paths.backups_dir(&self.game)).list()?;
+        if backups.is_empty() {
+            println!("{} has no backups yet", self.game);
+        }
+        for backup in backups {
+            let kind = if backup.is_snapshot() {
+                "  (snapshot)"
+            } else {
+                ""
+            };
+            println!(
+                "{}  {}  {}{}",
+                backup.id,
+                backup.local_time(),
+                format_size(backup.size),
+                kind
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    backups::format_size,
+    command::{Runnable, RunnableResult},
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Prune {
+    /// Don't remove anything, show how much space would be freed
+    #[cfg_attr(feature = "commandline", clap(long))]
+    dry_run: bool,
+}
+
+impl Runnable for Prune {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let repository = paths.repository();
+        // Nothing can be backed up into it until it's done, or the blobs of the new snapshot could be removed
+        let _lock = repository.lock()?;
+        let snapshots = paths.snapshots()?;
+        let removed = repository.prune(&snapshots, self.dry_run)?;
+        let freed: u64 = removed.iter().map(|(_, size)| size).sum();
+        let verb = if self.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        };
+        println!(
+            "{} {} blobs no snapshot uses, {}, {} snapshots are left",
+            verb,
+            removed.len(),
+            format_size(freed),
+            snapshots.len()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    command::{Runnable, RunnableError, RunnableResult},
+    game::GameConfig,
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct UseRepository {
+    /// Save name of the game
+    game: String,
+
+    /// Go back to storing the backups of the game as archives
+    #[cfg_attr(feature = "commandline", clap(long))]
+    off: bool,
+}
+
+impl Runnable for UseRepository {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        if !GameConfig::exists(paths, &self.game) {
+            return Err(RunnableError::UnknownGame(self.game.clone()));
+        }
+        let mut config = GameConfig::load(paths, &self.game)?;
+        config.repository = !self.off;
+        config.save(paths, &self.game)?;
+        if config.repository {
+            println!(
+                "New backups of {} go into the repository in {}",
+                self.game,
+                paths.repository_dir().display()
+            );
+        } else {
+            println!("New backups of {} are stored as archives", self.game);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    backups::{BackupStore, Retention},
+    command::{Runnable, RunnableError, RunnableResult},
+    game::GameConfig,
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct SetRetention {
+    /// Save name of the game
+    game: String,
+
+    #[cfg_attr(feature = "commandline", command(flatten))]
+    retention: Retention,
+
+    /// Forget the rules that were set before and keep all backups from now on
+    #[cfg_attr(feature = "commandline", clap(long))]
+    keep_all: bool,
+}
+
+impl Runnable for SetRetention {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        if !GameConfig::exists(paths, &self.game) {
+            return Err(RunnableError::UnknownGame(self.game.clone()));
+        }
+        let mut config = GameConfig::load(paths, &self.game)?;
+        if self.keep_all {
+            config.retention = Retention::default();
+        }
+        config.retention.merge(&self.retention);
+        config.save(paths, &self.game)?;
+
+        let retention = &config.retention;
+        if retention.is_empty() {
+            println!("Keeping all backups of {}", self.game);
+            return Ok(());
+        }
+        let rules: Vec<String> = [
+            retention.keep_last.map(|n| format!("the last {}", n)),
+            retention
+                .keep_daily
+                .map(|n| format!("one per day for {} days", n)),
+            retention
+                .keep_weekly
+                .map(|n| format!("one per week for {} weeks", n)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        println!("Backups of {} to keep: {}", self.game, rules.join(", "));
+
+        let store = BackupStore::new(paths.backups_dir(&self.game));
+        for removed in store.apply_retention(retention)? {
+            println!(
+                "Removed backup {} from {}",
+                removed.id,
+                removed.local_time()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use crate::{
+    backups::{format_size, BackupManifest, Layout},
+    command::{restore::describe, Runnable, RunnableResult},
+    paths::Paths,
+    steam::SteamData,
+};
+
+use super::find_backup;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Show {
+    /// Save name of the game
+    #[cfg_attr(feature = "commandline", clap(required_unless_present = "file"))]
+    game: Option<String>,
+
+    /// Id of the backup, if not specified the latest backup is shown
+    #[cfg_attr(feature = "commandline", clap(conflicts_with = "file"))]
+    backup: Option<String>,
+
+    /// Show a backup file from somewhere else instead of one from the backups of the game
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    file: Option<PathBuf>,
+
+    /// Print the manifest as JSON
+    #[cfg_attr(feature = "commandline", clap(long))]
+    json: bool,
+}
+
+impl Runnable for Show {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let path = match (&self.file, &self.game) {
+            (Some(file), _) => file.clone(),
+            (None, Some(game)) => find_backup(paths, game, self.backup.as_deref())?.path,
+            (None, None) => unreachable!("clap requires a game or a file"),
+        };
+        let Some(manifest) = BackupManifest::read(&path)? else {
+            println!("{} has no manifest", path.display());
+            return Ok(());
+        };
+        if self.json {
+            println!("{}", manifest.to_json());
+            return Ok(());
+        }
+
+        println!("{}", path.display());
+        println!("This is a {}", describe(&manifest));
+        if manifest.layout == Layout::Windows {
+            println!("The files are laid out like on Windows, the paths below are where they go in the prefix");
+        }
+        for (path, file) in &manifest.files {
+            println!(
+                "  {}  {:>10}  {}",
+                file.hash.get(..12).unwrap_or(&file.hash),
+                format_size(file.size),
+                path.display()
+            );
+        }
+        for path in &manifest.deleted {
+            println!("  deleted  {}", path.display());
+        }
+        Ok(())
+    }
+}
@@ -8,16 +8,33 @@ use std::{
 use exe::{Buffer, ResourceDirectory, VecPE};
 use serde::{Deserialize, Serialize};
 
-use crate::{paths::Paths, steam::SteamData};
+use crate::{
+    game::{save_name_for, GameConfig},
+    paths::Paths,
+    pe::ExeInfo,
+    steam::SteamData,
+};
 
-use super::{Runnable, RunnableResult};
+use super::{Runnable, RunnableError, RunnableResult};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "commandline", derive(clap::Args))]
 pub struct MakeDE {
+    /// Path to the game exe, or the name of an added game
     exe: PathBuf,
-    name: String,
+
+    /// Optional name to show in the desktop entry
+    /// If not specified, the name the game was added with or the product name from its version info will be used
+    name: Option<String>,
+
+    /// Optional save name to use
+    /// If not specified, the save name the exe was added with will be used,
+    /// or one picked like `game add` does if it wasn't added
     save_name: Option<String>,
+
+    /// Which exe of an added game the entry should launch, like a launcher or settings tool
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    target: Option<String>,
 }
 
 fn make_icon(exe: &Path, paths: &Paths, name: &str) -> PathBuf {
@@ -33,21 +50,105 @@ fn make_icon(exe: &Path, paths: &Paths, name: &str) -> PathBuf {
     path
 }
 
+/// Quote an argument for the `Exec` key, as described in the desktop entry spec
+fn quote_exec_arg(arg: &str) -> String {
+    if !arg.contains(|c: char| c.is_whitespace() || "\"'\\`$<>|&;*?#()~".contains(c)) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    for c in arg.chars() {
+        if "\"`$\\".contains(c) {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Describe the game with what its version info has to say about it
+fn comment(name: &str, info: Option<&ExeInfo>) -> String {
+    let Some(info) = info else {
+        return format!("Run {} with Proton", name);
+    };
+    let mut comment = match info.file_description().filter(|d| *d != name) {
+        Some(description) => description.to_string(),
+        None => format!("Run {} with Proton", name),
+    };
+    let details: Vec<String> = [
+        info.company_name().map(|c| c.to_string()),
+        info.product_version().map(|v| format!("version {}", v)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !details.is_empty() {
+        comment.push_str(&format!(" ({})", details.join(", ")));
+    }
+    comment
+}
+
 impl Runnable for MakeDE {
     fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
-        let save_name = self
-            .save_name
-            .as_deref()
-            .unwrap_or_else(|| self.exe.file_stem().unwrap().to_str().unwrap());
-        let mut de = DesktopEntry::new(self.name.clone());
-        de.comment = format!("Run {} with Proton", self.name);
-        de.exec = format!("proton-launch run -s {save_name} {} ", self.exe.display());
-        de.path = paths.run_dir(save_name).display().to_string();
-        let icon_path = make_icon(&self.exe, paths, &self.name);
+        let game_name = self.exe.to_string_lossy().to_string();
+        let is_game = GameConfig::is_added(paths, &self.exe);
+        let local = if paths.is_local() { "--local " } else { "" };
+
+        let (exe, save_name, exec) = if is_game {
+            let config = GameConfig::load(paths, &game_name)?;
+            let launch =
+                config
+                    .target(self.target.as_deref())
+                    .ok_or_else(|| match &self.target {
+                        Some(t) => RunnableError::UnknownTarget(game_name.clone(), t.clone()),
+                        None => RunnableError::NoExe,
+                    })?;
+            let mut exec = format!("proton-launch {local}run {}", quote_exec_arg(&game_name));
+            if let Some(target) = &self.target {
+                exec.push_str(&format!(" --target {}", quote_exec_arg(target)));
+            }
+            (launch.exe, game_name, exec)
+        } else {
+            if self.target.is_some() {
+                return Err(RunnableError::UnknownGame(game_name));
+            }
+            let save_name = self
+                .save_name
+                .clone()
+                .or_else(|| GameConfig::find_by_exe(paths, &self.exe))
+                .unwrap_or_else(|| save_name_for(paths, &self.exe));
+            let exec = format!(
+                "proton-launch {local}run -s {} {} ",
+                quote_exec_arg(&save_name),
+                quote_exec_arg(&self.exe.display().to_string())
+            );
+            (self.exe.clone(), save_name, exec)
+        };
+
+        let info = ExeInfo::inspect(&exe).ok();
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => {
+                let name = GameConfig::load(paths, &save_name)?
+                    .name
+                    .or_else(|| info.as_ref()?.display_name().map(str::to_string))
+                    .unwrap_or_else(|| save_name.clone());
+                match &self.target {
+                    Some(target) => format!("{} ({})", name, target),
+                    None => name,
+                }
+            }
+        };
+
+        let mut de = DesktopEntry::new(&name);
+        de.comment = comment(&name, info.as_ref());
+        de.exec = exec;
+        de.path = paths.run_dir(&save_name).display().to_string();
+        let icon_path = make_icon(&exe, paths, &name);
         de.icon = icon_path.display().to_string();
 
         {
-            let mut f = File::create(paths.application_entry(&self.name)).unwrap();
+            let mut f = File::create(paths.application_entry(&name)).unwrap();
             writeln!(f, "[Desktop Entry]").unwrap();
             let mut s = serde_ini::Serializer::new(serde_ini::Writer::new(
                 &mut f,
@@ -56,7 +157,7 @@ impl Runnable for MakeDE {
             de.serialize(&mut s).unwrap();
         }
         let command = Command::new("update-desktop-database")
-            .arg(paths.application_entry(&self.name).parent().unwrap())
+            .arg(paths.application_entry(&name).parent().unwrap())
             .spawn()
             .unwrap()
             .wait()
@@ -0,0 +1,52 @@
+use crate::{paths::Paths, steam::SteamData};
+
+use super::{Runnable, RunnableResult};
+
+pub mod add;
+pub mod add_target;
+pub mod link;
+pub mod list;
+pub mod remove;
+pub mod remove_target;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Game {
+    #[cfg_attr(feature = "commandline", command(subcommand))]
+    command: GameCommand,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Subcommand))]
+pub enum GameCommand {
+    /// Add a game, or change the settings of one that was already added
+    Add(add::Add),
+
+    /// List all added games
+    List(list::List),
+
+    /// Remove a game, this keeps its compat folder
+    Remove(remove::Remove),
+
+    /// Add another exe to a game that uses the same compat folder, like a launcher or settings tool
+    AddTarget(add_target::AddTarget),
+
+    /// Remove an exe that was added with `add-target`
+    RemoveTarget(remove_target::RemoveTarget),
+
+    /// Link a game to a Steam app or umu id, so proton applies its fixes for the game
+    Link(link::Link),
+}
+
+impl Runnable for Game {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        match &self.command {
+            GameCommand::Add(a) => a.run(paths, steam_data),
+            GameCommand::List(l) => l.run(paths, steam_data),
+            GameCommand::Remove(r) => r.run(paths, steam_data),
+            GameCommand::AddTarget(a) => a.run(paths, steam_data),
+            GameCommand::RemoveTarget(r) => r.run(paths, steam_data),
+            GameCommand::Link(l) => l.run(paths, steam_data),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use crate::{
+    command::{Runnable, RunnableResult},
+    game::{save_name_for, GameConfig},
+    lnk::{is_link, ShellLink},
+    paths::Paths,
+    pe::{ExeInfo, Suggestion},
+    proton::ProtonVersion,
+    steam::SteamData,
+    wine::find_compat_dir,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Add {
+    /// Path to the game exe, or a `.lnk` shortcut to it
+    exe: PathBuf,
+
+    /// Optional save name to use
+    /// If not specified, the product name from the exe's version info will be used,
+    /// or the name of the exe or shortcut without the extension if it has none
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    save_name: Option<String>,
+
+    /// Optional name to show for the game
+    /// If not specified, the product name from the exe's version info will be used
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    name: Option<String>,
+
+    /// Keep the compat folder of this game next to the exe, like `--local` does for a single command
+    #[cfg_attr(feature = "commandline", clap(long))]
+    local: bool,
+
+    /// Run the game in the directory of the exe, like `run --here`
+    #[cfg_attr(feature = "commandline", clap(long))]
+    here: bool,
+
+    /// Optional proton version to always run this game with
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    proton: Option<ProtonVersion>,
+}
+
+impl Runnable for Add {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let path = std::fs::canonicalize(&self.exe)?;
+        let save_name = match &self.save_name {
+            Some(save_name) => save_name.clone(),
+            None => save_name_for(paths, &path),
+        };
+        let save_name = save_name.as_str();
+
+        // Adding a game again replaces how it is launched, other settings like its ids and backups are kept
+        let mut config = GameConfig::load(paths, save_name)?;
+        let exe = if is_link(&path) {
+            let prefix = paths.compat_path(save_name, None)?.join("pfx");
+            let resolved = ShellLink::resolve_file(&path, || prefix)?;
+            println!("Shortcut points to {}", resolved.exe.display());
+            config.args = resolved.args;
+            config.working_dir = resolved.working_dir;
+            config.compat_dir = resolved.compat_dir;
+            resolved.exe
+        } else {
+            // Nothing is left over from a shortcut the game was added with before
+            config.args = Vec::new();
+            config.working_dir = None;
+            config.compat_dir = find_compat_dir(&path);
+            path.clone()
+        };
+        config.exe = Some(exe.clone());
+        config.local = self.local || paths.is_local();
+        config.proton = self.proton.or(config.proton);
+        config.here = self.here;
+
+        let info = ExeInfo::inspect(&exe).ok();
+        if let Some(name) = &self.name {
+            config.name = Some(name.clone());
+        } else if config.name.is_none() {
+            config.name = info
+                .as_ref()
+                .and_then(|i| i.display_name())
+                .map(str::to_string);
+        }
+        config.save(paths, save_name)?;
+
+        println!("Added {} ({})", save_name, exe.display());
+        if let Some(info) = info {
+            for suggestion in info.suggestions() {
+                if suggestion == Suggestion::RunHere && config.here {
+                    continue;
+                }
+                println!("Suggestion: {}", suggestion);
+            }
+        }
+        println!(
+            "Compat folder: {}",
+            paths.compat_dir(save_name, Some(&exe))?.display()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use crate::{
+    command::{Runnable, RunnableError, RunnableResult},
+    game::{GameConfig, LaunchTarget},
+    lnk::{is_link, ShellLink},
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct AddTarget {
+    /// Save name of the game to add the target to
+    game: String,
+
+    /// Name of the target, like `launcher`, `settings` or `server`
+    target: String,
+
+    /// Path to the exe of the target, or a `.lnk` shortcut to it
+    exe: PathBuf,
+
+    /// Optional directory to run the target in
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    working_dir: Option<PathBuf>,
+
+    /// Args to always pass to the target
+    #[cfg_attr(feature = "commandline", clap(last = true))]
+    args: Vec<String>,
+}
+
+impl Runnable for AddTarget {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        if !GameConfig::exists(paths, &self.game) {
+            return Err(RunnableError::UnknownGame(self.game.clone()));
+        }
+        let mut config = GameConfig::load(paths, &self.game)?;
+
+        let path = std::fs::canonicalize(&self.exe)?;
+        let target = if is_link(&path) {
+            let prefix = paths.compat_path(&self.game, None)?.join("pfx");
+            let resolved = ShellLink::resolve_file(&path, || prefix)?;
+            println!("Shortcut points to {}", resolved.exe.display());
+            let mut args = resolved.args;
+            args.extend(self.args.iter().cloned());
+            LaunchTarget {
+                exe: resolved.exe,
+                args,
+                working_dir: self.working_dir.clone().or(resolved.working_dir),
+            }
+        } else {
+            LaunchTarget {
+                exe: path,
+                args: self.args.clone(),
+                working_dir: self.working_dir.clone(),
+            }
+        };
+
+        println!(
+            "Added {} to {} ({}), run it with `proton-launch run {} --target {}`",
+            self.target,
+            self.game,
+            target.exe.display(),
+            self.game,
+            self.target
+        );
+        config.targets.insert(self.target.clone(), target);
+        config.save(paths, &self.game)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+use crate::{
+    command::{prompt::choose, Runnable, RunnableError, RunnableResult},
+    game::GameConfig,
+    paths::Paths,
+    steam::{AppManifest, SteamData},
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Link {
+    /// Save name of the game to link
+    game: String,
+
+    /// Steam app id of the game
+    #[cfg_attr(feature = "commandline", clap(short, long, conflicts_with = "find"))]
+    app_id: Option<u64>,
+
+    /// Find the Steam app id by matching the game against the apps installed in Steam
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    find: bool,
+
+    /// umu game id like `umu-1234`, for games from GOG, Epic or other stores
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    umu_id: Option<String>,
+
+    /// Store the game is from, like `gog` or `egs`
+    #[cfg_attr(feature = "commandline", clap(long, requires = "umu_id"))]
+    store: Option<String>,
+
+    /// Name of the game in the ludusavi manifest, so backups contain the saves it lists
+    #[cfg_attr(feature = "commandline", clap(long))]
+    ludusavi: Option<String>,
+
+    /// Remove all ids from the game
+    #[cfg_attr(
+        feature = "commandline",
+        clap(long, conflicts_with_all = ["app_id", "find", "umu_id", "ludusavi"])
+    )]
+    unlink: bool,
+}
+
+/// Names are compared without case, spaces or punctuation, so `Half-Life 2` matches `half_life2`
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether the install dir of the app has a file with the same name as the exe
+fn has_exe(app: &AppManifest, exe_name: &str) -> bool {
+    WalkDir::new(&app.install_dir)
+        .max_depth(4)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .any(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(exe_name)
+        })
+}
+
+fn find_app(config: &GameConfig, save_name: &str, steam_data: &SteamData) -> RunnableResult<u64> {
+    let exe_name = config
+        .exe
+        .as_deref()
+        .and_then(Path::file_name)
+        .map(|n| n.to_string_lossy().to_string());
+    let names: Vec<String> = [config.name.as_deref(), Some(save_name)]
+        .into_iter()
+        .flatten()
+        .map(normalize)
+        .collect();
+
+    let candidates: Vec<AppManifest> = steam_data
+        .app_manifests()
+        .into_iter()
+        .filter(|app| {
+            names.contains(&normalize(&app.name))
+                || exe_name.as_deref().is_some_and(|exe| has_exe(app, exe))
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [] => Err(RunnableError::NoSteamMatch(save_name.to_string())),
+        [app] => Ok(app.app_id),
+        _ => {
+            let options: Vec<String> = candidates
+                .iter()
+                .map(|app| format!("{} ({})", app.name, app.app_id))
+                .collect();
+            let choice = choose("Several Steam apps match, which one is it?", &options)?;
+            Ok(candidates[choice].app_id)
+        }
+    }
+}
+
+impl Runnable for Link {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        if !GameConfig::exists(paths, &self.game) {
+            return Err(RunnableError::UnknownGame(self.game.clone()));
+        }
+        let mut config = GameConfig::load(paths, &self.game)?;
+
+        if self.unlink {
+            config.steam_app_id = None;
+            config.umu_id = None;
+            config.store = None;
+            config.ludusavi = None;
+        }
+        if self.find {
+            config.steam_app_id = Some(find_app(&config, &self.game, steam_data)?);
+        } else if let Some(app_id) = self.app_id {
+            config.steam_app_id = Some(app_id);
+        }
+        if let Some(umu_id) = &self.umu_id {
+            config.umu_id = Some(umu_id.clone());
+            config.store = self.store.clone();
+        }
+        if let Some(ludusavi) = &self.ludusavi {
+            config.ludusavi = Some(ludusavi.clone());
+        }
+        config.save(paths, &self.game)?;
+
+        if let Some(ludusavi) = &config.ludusavi {
+            println!("{} is {} in the ludusavi manifest", self.game, ludusavi);
+        }
+        let env = config.game_id_env();
+        if env.is_empty() {
+            println!("{} is not linked to any game id", self.game);
+        } else {
+            println!("{} runs with:", self.game);
+            for (key, value) in env {
+                println!("  {}={}", key, value);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{
+    command::{Runnable, RunnableResult},
+    game::GameConfig,
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct List {}
+
+impl Runnable for List {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        for save_name in GameConfig::list(paths)? {
+            let config = GameConfig::load(paths, &save_name)?;
+            let exe = config
+                .exe
+                .map(|e| e.display().to_string())
+                .unwrap_or_else(|| "no exe".to_string());
+            let local = if config.local { " (local)" } else { "" };
+            match config.name {
+                Some(name) if name != save_name => {
+                    println!("{} [{}]: {}{}", save_name, name, exe, local)
+                }
+                _ => println!("{}: {}{}", save_name, exe, local),
+            }
+            for (target, launch) in config.targets {
+                println!("  {}: {}", target, launch.exe.display());
+            }
+        }
+        Ok(())
+    }
+}
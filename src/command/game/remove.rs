@@ -0,0 +1,24 @@
+use crate::{
+    command::{Runnable, RunnableError, RunnableResult},
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Remove {
+    /// Save name of the game to remove
+    game: String,
+}
+
+impl Runnable for Remove {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let config = paths.game_config(&self.game);
+        if !config.is_file() {
+            return Err(RunnableError::UnknownGame(self.game.clone()));
+        }
+        std::fs::remove_file(config)?;
+        println!("Removed {}", self.game);
+        Ok(())
+    }
+}
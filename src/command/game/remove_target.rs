@@ -0,0 +1,34 @@
+use crate::{
+    command::{Runnable, RunnableError, RunnableResult},
+    game::GameConfig,
+    paths::Paths,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct RemoveTarget {
+    /// Save name of the game to remove the target from
+    game: String,
+
+    /// Name of the target to remove
+    target: String,
+}
+
+impl Runnable for RemoveTarget {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        if !GameConfig::exists(paths, &self.game) {
+            return Err(RunnableError::UnknownGame(self.game.clone()));
+        }
+        let mut config = GameConfig::load(paths, &self.game)?;
+        if config.targets.remove(&self.target).is_none() {
+            return Err(RunnableError::UnknownTarget(
+                self.game.clone(),
+                self.target.clone(),
+            ));
+        }
+        config.save(paths, &self.game)?;
+        println!("Removed {} from {}", self.target, self.game);
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use crate::{paths::Paths, pe::ExeInfo, steam::SteamData};
+
+use super::{Runnable, RunnableResult};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Inspect {
+    /// Path to the exe to inspect
+    exe: PathBuf,
+
+    /// Print the info as JSON
+    #[cfg_attr(feature = "commandline", clap(long))]
+    json: bool,
+}
+
+impl Runnable for Inspect {
+    fn run(&self, _paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let info = ExeInfo::inspect(&self.exe)?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&info).unwrap());
+            return Ok(());
+        }
+
+        println!("=== {} ===", self.exe.display());
+        println!(
+            "Architecture: {}",
+            if info.is_64_bit { "64-bit" } else { "32-bit" }
+        );
+        println!(".NET: {}", info.dotnet);
+        match info.engine {
+            Some(engine) => println!("Engine: {}", engine),
+            None => println!("Engine: unknown"),
+        }
+        println!("Imports: {}", info.imports.join(", "));
+        if !info.version_info.is_empty() {
+            println!("Version info:");
+            for (key, value) in &info.version_info {
+                println!("  {}: {}", key, value);
+            }
+        }
+        let suggestions = info.suggestions();
+        if !suggestions.is_empty() {
+            println!("Suggestions:");
+            for s in suggestions {
+                println!("  - {}", s);
+            }
+        }
+        Ok(())
+    }
+}
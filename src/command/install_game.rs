@@ -0,0 +1,168 @@
+use std::{
+    collections::HashSet,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    baseline::Baseline,
+    game::GameConfig,
+    launch::LaunchPlan,
+    lnk::{is_link, ShellLink},
+    paths::Paths,
+    proton::ProtonVersion,
+    steam::SteamData,
+};
+
+use super::{
+    prefix::init::ensure_prefix,
+    prompt::choose,
+    run::{run_session, select_proton},
+    Runnable, RunnableError, RunnableResult,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct InstallGame {
+    /// Path to the installer, either an exe or an msi
+    installer: PathBuf,
+
+    /// Save name to register the installed game as
+    save_name: String,
+
+    /// Optional proton version to use, the game will keep using this version
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    proton: Option<ProtonVersion>,
+
+    /// Throw away the prefix the save name already has and install into a new one
+    #[cfg_attr(feature = "commandline", clap(long))]
+    force: bool,
+}
+
+impl Runnable for InstallGame {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        let (selected, proton_path) = select_proton(self.proton, steam_data)?;
+        let installer = std::fs::canonicalize(&self.installer)?;
+
+        let compat_dir = paths.compat_dir(&self.save_name, None)?;
+        let _lock = paths.lock_compat_dir(&compat_dir)?;
+        // Anything already in the prefix would be mistaken for what the installer made
+        if !is_fresh(&compat_dir)? {
+            if !self.force {
+                return Err(RunnableError::PrefixExists(
+                    self.save_name.clone(),
+                    compat_dir,
+                ));
+            }
+            println!("Removing the old prefix in {}", compat_dir.display());
+            std::fs::remove_dir_all(&compat_dir)?;
+            std::fs::create_dir_all(&compat_dir)?;
+        }
+        let run_dir = paths.run_dir(&self.save_name);
+        let mut plan = LaunchPlan::new(
+            selected,
+            proton_path,
+            steam_data,
+            self.save_name.clone(),
+            compat_dir.clone(),
+            run_dir.clone(),
+            installer.clone(),
+        );
+        ensure_prefix(&plan)?;
+        let prefix = compat_dir.join("pfx");
+        let before: HashSet<PathBuf> = find_launchables(&prefix).into_iter().collect();
+
+        println!("Installing {} with {}", installer.display(), selected);
+        if installer
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("msi"))
+        {
+            plan = plan.run_other("msiexec");
+            plan.argv.push("/i".to_string());
+            plan.argv.push(installer.to_string_lossy().to_string());
+        }
+        // Installers like to exit with odd codes, so look at what they left behind either way
+        if let Err(e) = run_session(&plan, &run_dir) {
+            match e {
+                RunnableError::GameExited(_) => println!("Warning: {}", e),
+                e => return Err(e),
+            }
+        }
+        // The game files are part of the prefix now, backups only need what playing it changes
+        Baseline::scan(&compat_dir)?.save(&compat_dir)?;
+
+        let mut new: Vec<PathBuf> = find_launchables(&prefix)
+            .into_iter()
+            .filter(|p| !before.contains(p))
+            .collect();
+        new.sort();
+        if new.is_empty() {
+            return Err(RunnableError::NothingInstalled);
+        }
+        let options: Vec<String> = new
+            .iter()
+            .map(|p| p.strip_prefix(&prefix).unwrap().display().to_string())
+            .collect();
+        let picked = &new[choose("Which one launches the game?", &options)?];
+
+        let mut config = GameConfig::load(paths, &self.save_name)?;
+        if is_link(picked) {
+            let resolved = ShellLink::resolve_file(picked, || prefix.clone())?;
+            config.exe = Some(resolved.exe);
+            config.args = resolved.args;
+            config.working_dir = resolved.working_dir;
+        } else {
+            config.exe = Some(picked.clone());
+        }
+        config.compat_dir = Some(compat_dir);
+        config.proton = Some(selected);
+        config.save(paths, &self.save_name)?;
+
+        println!(
+            "Added {}, run it with `proton-launch run {}`",
+            self.save_name, self.save_name
+        );
+        Ok(())
+    }
+}
+
+/// Whether the compat dir has no prefix yet, or one nothing changed in since it was created
+fn is_fresh(compat_dir: &Path) -> std::io::Result<bool> {
+    if !compat_dir.join("pfx").exists() {
+        return Ok(true);
+    }
+    if !Baseline::exists(compat_dir) {
+        return Ok(false);
+    }
+    Ok(Baseline::load(compat_dir)?.changes(compat_dir)?.is_empty())
+}
+
+/// All PE executables and shortcuts in the prefix, skipping the parts wine itself fills
+fn find_launchables(prefix: &Path) -> Vec<PathBuf> {
+    let drive_c = prefix.join("drive_c");
+    walkdir::WalkDir::new(&drive_c)
+        .into_iter()
+        .filter_entry(|e| {
+            let path = e.path().strip_prefix(&drive_c).unwrap();
+            !(path.starts_with("windows") || path.ends_with("Temp"))
+        })
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|p| is_link(p) || is_pe(p))
+        .collect()
+}
+
+fn is_pe(path: &Path) -> bool {
+    if !path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("exe"))
+    {
+        return false;
+    }
+    let mut magic = [0; 2];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok()
+        && &magic == b"MZ"
+}
@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-use crate::{paths::Paths, steam::SteamData};
+use crate::{game::save_name_for, paths::Paths, steam::SteamData};
 
 use super::{Runnable, RunnableResult};
 
@@ -23,7 +23,7 @@ pub struct MoveCompat {
     exe: PathBuf,
 
     /// Optional save name to use
-    /// If not specified, the game exe without the extension will be used
+    /// If not specified, it is picked like `game add` does, from the product name of the exe or its file name
     #[cfg_attr(feature = "commandline", clap(short, long))]
     save_name: Option<String>,
 }
@@ -32,10 +32,13 @@ impl Runnable for MoveCompat {
     fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
         let save_name = self
             .save_name
-            .as_deref()
-            .unwrap_or_else(|| self.exe.file_stem().unwrap().to_str().unwrap());
-        let global_compat_dir = paths.compat_dir(save_name);
-        let local_compat_dir = self.exe.parent().unwrap().join("compat");
+            .clone()
+            .unwrap_or_else(|| save_name_for(paths, &self.exe));
+        let save_name = save_name.as_str();
+        let global_compat_dir = paths.global_compat_dir(save_name);
+        let local_compat_dir = paths.local_compat_dir(&self.exe);
+        let _global_lock = paths.lock_compat_dir(&global_compat_dir)?;
+        let _local_lock = paths.lock_compat_dir(&local_compat_dir)?;
         println!("global exists: {}", global_compat_dir.exists());
         println!("local exists: {}", local_compat_dir.exists());
         match self.direction {
@@ -66,9 +69,9 @@ fn copy_file_tree(source: &Path, dest: &Path) -> Result<(), std::io::Error> {
         let entry = entry?;
         let path = entry.path().strip_prefix(source).unwrap();
         if entry.file_type().is_dir() {
-            std::fs::create_dir_all(&dest.join(path))?;
+            std::fs::create_dir_all(dest.join(path))?;
         } else if entry.file_type().is_file() {
-            std::fs::copy(entry.path(), &dest.join(path))?;
+            std::fs::copy(entry.path(), dest.join(path))?;
         }
     }
 
@@ -0,0 +1,47 @@
+use crate::{paths::Paths, steam::SteamData};
+
+use super::{Runnable, RunnableResult};
+
+pub mod diff;
+pub mod export;
+pub mod import;
+pub mod init;
+pub mod tool;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Prefix {
+    #[cfg_attr(feature = "commandline", command(subcommand))]
+    command: PrefixCommand,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Subcommand))]
+pub enum PrefixCommand {
+    /// Create the prefix of a game and record which files it starts out with, so backups only contain what the game added
+    Init(init::Init),
+
+    /// Run a wine tool like winecfg or regedit inside the prefix of a game
+    Tool(tool::Tool),
+
+    /// Pack the whole compat dir of a game into an archive, to move it to another machine
+    Export(export::Export),
+
+    /// Unpack an archive made with `prefix export`, pointing the paths in it at this machine
+    Import(import::Import),
+
+    /// Show which files and registry keys changed in the prefix of a game since its baseline
+    Diff(diff::Diff),
+}
+
+impl Runnable for Prefix {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        match &self.command {
+            PrefixCommand::Init(i) => i.run(paths, steam_data),
+            PrefixCommand::Tool(t) => t.run(paths, steam_data),
+            PrefixCommand::Export(e) => e.run(paths, steam_data),
+            PrefixCommand::Import(i) => i.run(paths, steam_data),
+            PrefixCommand::Diff(d) => d.run(paths, steam_data),
+        }
+    }
+}
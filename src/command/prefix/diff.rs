@@ -0,0 +1,121 @@
+use crate::{
+    baseline::Baseline,
+    command::{Runnable, RunnableError, RunnableResult},
+    paths::Paths,
+    registry::{hive_of, KeyDiff, RegistryFile, ValueDiff, HIVES},
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Diff {
+    /// Save name of the game whose prefix to compare against its baseline
+    game: String,
+
+    /// Only show the changes to the registry, not the files
+    #[cfg_attr(feature = "commandline", clap(long))]
+    registry: bool,
+
+    /// Only show these registry keys and the keys below them, like `HKEY_CURRENT_USER/Software/Vendor`.
+    /// Can be given multiple times
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    key: Vec<String>,
+}
+
+impl Runnable for Diff {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let compat_dir = paths.compat_dir(&self.game, None)?;
+        if !Baseline::exists(&compat_dir) {
+            return Err(RunnableError::NoBaseline(self.game.clone()));
+        }
+        let baseline = Baseline::load(&compat_dir)?;
+
+        if !self.registry && self.key.is_empty() {
+            let changes = baseline.changes(&compat_dir)?;
+            if changes.is_empty() {
+                println!("No files changed since the baseline");
+            }
+            for (sign, files) in [
+                ('+', &changes.added),
+                ('~', &changes.modified),
+                ('-', &changes.deleted),
+            ] {
+                for file in files {
+                    println!("{} {}", sign, file.display());
+                }
+            }
+        }
+
+        let mut hives = Vec::new();
+        for (hive, root) in HIVES {
+            hives.push((hive, root, Baseline::hive(&compat_dir, hive)?));
+        }
+        if hives.iter().all(|(_, _, old)| old.is_none()) {
+            println!("The baseline is from before the registry was recorded, run `prefix init` again to compare it");
+            return Ok(());
+        }
+        for (hive, root, old) in hives {
+            let keys: Vec<String> = self
+                .key
+                .iter()
+                .filter_map(|k| hive_of(k).filter(|(h, _)| *h == hive).map(|(_, k)| k))
+                .collect();
+            if !self.key.is_empty() && keys.is_empty() {
+                continue;
+            }
+            let path = compat_dir.join("pfx").join(hive);
+            if old.is_none() && !path.is_file() {
+                continue;
+            }
+            let old = match old {
+                Some(text) => RegistryFile::parse(&text)?,
+                None => RegistryFile::default(),
+            };
+            let new = match path.is_file() {
+                true => RegistryFile::load(&path)?,
+                false => RegistryFile::default(),
+            };
+            let diffs = match keys.is_empty() {
+                true => RegistryFile::diff(&old, &new),
+                false => RegistryFile::diff(&old.extract(&keys), &new.extract(&keys)),
+            };
+            if diffs.is_empty() {
+                println!("No registry keys in {} changed since the baseline", root);
+                continue;
+            }
+            println!("Registry keys in {}:", root);
+            for diff in diffs {
+                match diff {
+                    KeyDiff::Added(key) => {
+                        println!("+ [{}]", key.path);
+                        for value in &key.values {
+                            println!("    + {}", value);
+                        }
+                    }
+                    KeyDiff::Removed(key) => println!("- [{}]", key.path),
+                    KeyDiff::Changed { path, values } => {
+                        println!("~ [{}]", path);
+                        for value in &values {
+                            println!("    {}", describe_value(value));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A changed value as one line, like `~ "Volume"=dword:00000003 -> dword:00000005`
+fn describe_value(diff: &ValueDiff) -> String {
+    let name = match &diff.name {
+        Some(name) => format!("\"{}\"", name),
+        None => "@".to_string(),
+    };
+    match (&diff.old, &diff.new) {
+        (Some(old), Some(new)) => format!("~ {}={} -> {}", name, old, new),
+        (None, Some(new)) => format!("+ {}={}", name, new),
+        (Some(old), None) => format!("- {}={}", name, old),
+        (None, None) => format!("  {}", name),
+    }
+}
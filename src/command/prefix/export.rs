@@ -0,0 +1,101 @@
+use std::{fs::File, path::PathBuf};
+
+use crate::{
+    command::{run::select_proton, Runnable, RunnableError, RunnableResult},
+    game::GameConfig,
+    paths::Paths,
+    prefix_archive::{is_sparse, PrefixManifest, PREFIX_MANIFEST},
+    proton::prefix_version,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Export {
+    /// Save name of the game whose prefix to export
+    game: String,
+
+    /// Where to write the archive, if not specified it is `<game>.prefix.tar.zst` in the current folder
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    output: Option<PathBuf>,
+}
+
+impl Runnable for Export {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        let config = GameConfig::load(paths, &self.game)?;
+        let compat_dir = paths.compat_dir(&self.game, None)?;
+        if !compat_dir.join("pfx").is_dir() {
+            return Err(RunnableError::NoPrefix(self.game.clone()));
+        }
+        let _lock = paths.lock_compat_dir(&compat_dir)?;
+
+        let mut manifest = PrefixManifest::new(&self.game, &compat_dir, &steam_data.path);
+        manifest.name = config.name.clone();
+        manifest.proton = prefix_version(&compat_dir).or(config.proton.map(|p| p.to_string()));
+        manifest.proton_path = select_proton(config.proton, steam_data)
+            .ok()
+            .map(|(_, path)| path);
+
+        // Symlinks are stored as they are, `dosdevices` and the dlls proton links in only make sense that way
+        let mut entries = Vec::new();
+        let walker = walkdir::WalkDir::new(&compat_dir)
+            .min_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| e.file_name() != ".restore");
+        for entry in walker {
+            let entry = entry.map_err(std::io::Error::from)?;
+            let path = entry
+                .path()
+                .strip_prefix(&compat_dir)
+                .unwrap()
+                .to_path_buf();
+            if is_sparse(&entry.metadata().map_err(std::io::Error::from)?) {
+                manifest.sparse.push(path.clone());
+            }
+            entries.push((entry.into_path(), path));
+        }
+
+        let output = self
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("{}.prefix.tar.zst", self.game)));
+        println!(
+            "Exporting {} ({} entries) to {}",
+            compat_dir.display(),
+            entries.len(),
+            output.display()
+        );
+        let f = File::create(&output)?;
+        let w = zstd::Encoder::new(f, 3)?;
+        let mut t = tar::Builder::new(w);
+        t.follow_symlinks(false);
+        let json = manifest.to_json();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(json.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(manifest.created as u64);
+        header.set_cksum();
+        t.append_data(&mut header, PREFIX_MANIFEST, json.as_bytes())?;
+        for (source, path) in &entries {
+            t.append_path_with_name(source, path)?;
+        }
+        t.into_inner()?.finish()?;
+
+        if let Some(proton) = &manifest.proton {
+            println!("The prefix was last used with proton {}", proton);
+        }
+        if !manifest.sparse.is_empty() {
+            println!(
+                "{} sparse files will get their holes back on import",
+                manifest.sparse.len()
+            );
+        }
+        println!(
+            "Exported the prefix of {} to {}",
+            self.game,
+            output.display()
+        );
+        Ok(())
+    }
+}
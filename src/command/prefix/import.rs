@@ -0,0 +1,178 @@
+use std::{
+    fs::{File, Permissions},
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::{
+    command::{
+        restore::{is_plain, Staging},
+        run::select_proton,
+        Runnable, RunnableError, RunnableResult,
+    },
+    game::GameConfig,
+    lock::with_suffix,
+    paths::Paths,
+    prefix_archive::{write_sparse, PathRewriter, PrefixManifest},
+    proton::ProtonVersion,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Import {
+    /// Archive made with `prefix export`
+    archive: PathBuf,
+
+    /// Save name of the game to import the prefix for, if not specified the one it was exported from is used
+    game: Option<String>,
+
+    /// Proton version the links into proton should point at, if not specified the one of the game is used
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    proton: Option<ProtonVersion>,
+
+    /// Replace the prefix the game already has
+    #[cfg_attr(feature = "commandline", clap(long))]
+    force: bool,
+}
+
+impl Runnable for Import {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        let manifest = PrefixManifest::read(&self.archive)?
+            .ok_or_else(|| RunnableError::NotAPrefixArchive(self.archive.clone()))?;
+        let save_name = self.game.as_deref().unwrap_or(&manifest.save_name);
+        let config = GameConfig::load(paths, save_name)?;
+        let compat_dir = paths.compat_dir(save_name, None)?;
+        let _lock = paths.lock_compat_dir(&compat_dir)?;
+        let in_use = compat_dir.is_dir() && compat_dir.read_dir()?.next().is_some();
+        if in_use && !self.force {
+            return Err(RunnableError::PrefixExists(
+                save_name.to_string(),
+                compat_dir.clone(),
+            ));
+        }
+
+        println!(
+            "Importing the prefix of {} exported from {} on {}",
+            manifest.save_name,
+            manifest.compat_dir.display(),
+            manifest.host
+        );
+        if let Some(proton) = &manifest.proton {
+            println!("It was last used with proton {}", proton);
+        }
+
+        let mut rewriter = PathRewriter::new();
+        rewriter.add(&manifest.compat_dir, &compat_dir);
+        let proton_path = select_proton(self.proton.or(config.proton), steam_data)
+            .ok()
+            .map(|(_, path)| path);
+        if let (Some(old), Some(new)) = (&manifest.proton_path, &proton_path) {
+            rewriter.add(old, new);
+        }
+        rewriter.add(&manifest.steam_path, &steam_data.path);
+        if let (Some(old), Some(new)) = (&manifest.home, dirs::home_dir()) {
+            rewriter.add(old, &new);
+        }
+
+        // Next to the compat dir, so it can be renamed into place once everything is unpacked
+        let staging = Staging::new(with_suffix(&compat_dir, ".import"))?;
+        let d = zstd::Decoder::new(File::open(&self.archive)?)?;
+        let mut archive = tar::Archive::new(d);
+        archive.set_preserve_permissions(true);
+        let mut dirs = Vec::new();
+        let mut links = Vec::new();
+        let mut files = 0;
+        for entry in archive.entries()?.skip(1) {
+            let mut entry = entry?;
+            let path = entry.path()?.to_path_buf();
+            if !is_plain(&path) {
+                return Err(RunnableError::UnsafeBackupEntry(path));
+            }
+            let dest = staging.0.join(&path);
+            let entry_type = entry.header().entry_type();
+            let mode = entry.header().mode()?;
+            if entry_type.is_dir() {
+                std::fs::create_dir_all(&dest)?;
+                dirs.push((dest, mode));
+            } else if entry_type.is_symlink() {
+                // tar stores a link to `/`, like the `z:` drive, as `//`
+                let target: PathBuf = entry
+                    .link_name()?
+                    .ok_or_else(|| RunnableError::NotAPrefixArchive(self.archive.clone()))?
+                    .components()
+                    .collect();
+                links.push((dest, target));
+            } else if entry_type.is_file() {
+                std::fs::create_dir_all(dest.parent().unwrap())?;
+                if manifest.sparse.contains(&path) {
+                    let size = entry.header().size()?;
+                    let mtime = entry.header().mtime()?;
+                    let f = write_sparse(&mut entry, &dest, size)?;
+                    f.set_permissions(Permissions::from_mode(mode))?;
+                    f.set_modified(UNIX_EPOCH + Duration::from_secs(mtime))?;
+                } else {
+                    entry.unpack(&dest)?;
+                }
+                files += 1;
+            } else {
+                println!(
+                    "Warning: skipping {}, it is not a file, folder or symlink",
+                    path.display()
+                );
+            }
+        }
+
+        // Links are only made once everything else is in place, so nothing gets written through them
+        let mut rewritten_links = 0;
+        for (dest, target) in &links {
+            let target = match rewriter.rewrite_path(target) {
+                Some(new) if target.is_absolute() => {
+                    rewritten_links += 1;
+                    new
+                }
+                _ => target.clone(),
+            };
+            std::fs::create_dir_all(dest.parent().unwrap())?;
+            std::os::unix::fs::symlink(target, dest)?;
+        }
+        // Folders last, one without write permission would have stopped everything from going into it
+        for (dir, mode) in dirs.iter().rev() {
+            std::fs::set_permissions(dir, Permissions::from_mode(*mode))?;
+        }
+
+        let mut rewritten_registry = 0;
+        for hive in ["system.reg", "user.reg", "userdef.reg"] {
+            let path = staging.0.join("pfx").join(hive);
+            let Ok(text) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let (text, count) = rewriter.rewrite_registry(&text);
+            if count > 0 {
+                std::fs::write(&path, text)?;
+                rewritten_registry += count;
+            }
+        }
+
+        if compat_dir.exists() {
+            if in_use {
+                std::fs::remove_dir_all(&compat_dir)?;
+            } else {
+                std::fs::remove_dir(&compat_dir)?;
+            }
+        }
+        std::fs::rename(&staging.0, &compat_dir)?;
+        println!(
+            "Imported {} files and {} symlinks into {}",
+            files,
+            links.len(),
+            compat_dir.display()
+        );
+        println!(
+            "Rewrote {} symlinks and {} paths in the registry",
+            rewritten_links, rewritten_registry
+        );
+        Ok(())
+    }
+}
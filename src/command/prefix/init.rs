@@ -0,0 +1,80 @@
+use std::{path::PathBuf, process::Command};
+
+use crate::{
+    baseline::Baseline,
+    command::{run::select_proton, Runnable, RunnableError, RunnableResult},
+    game::GameConfig,
+    launch::LaunchPlan,
+    paths::Paths,
+    proton::{wine_bin_dir, ProtonVersion},
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Init {
+    /// Save name of the game whose prefix to create
+    game: String,
+
+    /// Optional proton version to use
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    proton: Option<ProtonVersion>,
+}
+
+impl Runnable for Init {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        let config = GameConfig::load(paths, &self.game)?;
+        let (selected, proton_path) = select_proton(self.proton.or(config.proton), steam_data)?;
+
+        let compat_dir = paths.compat_dir(&self.game, None)?;
+        let _lock = paths.lock_compat_dir(&compat_dir)?;
+        if compat_dir.join("pfx").is_dir() {
+            println!(
+                "Warning: {} already exists, files in it now won't be picked up by backups",
+                compat_dir.join("pfx").display()
+            );
+        }
+        init_prefix(&LaunchPlan::new(
+            selected,
+            proton_path,
+            steam_data,
+            self.game.clone(),
+            compat_dir,
+            paths.run_dir(&self.game),
+            PathBuf::from("wineboot"),
+        ))
+    }
+}
+
+/// Let proton create the prefix the plan runs in, wait until wine is done with it and record the baseline for backups
+pub fn init_prefix(plan: &LaunchPlan) -> RunnableResult<()> {
+    let compat_dir = &plan.compat_dir;
+    println!("Setting up the prefix in {}", compat_dir.display());
+    let status = plan
+        .run_other("wineboot")
+        .command()
+        .status()
+        .map_err(RunnableError::SpawnError)?;
+    if !status.success() {
+        return Err(RunnableError::PrefixInit(status));
+    }
+    // wineboot returns before the services it started are done writing to the prefix
+    Command::new(wine_bin_dir(&plan.proton_path).join("wineserver"))
+        .arg("-w")
+        .env("WINEPREFIX", compat_dir.join("pfx"))
+        .status()
+        .map_err(RunnableError::SpawnError)?;
+
+    let baseline = Baseline::scan(compat_dir)?;
+    baseline.save(compat_dir)?;
+    println!("Recorded {} files in the baseline", baseline.len());
+    Ok(())
+}
+
+/// Create the prefix the plan runs in if it doesn't exist yet, so it always starts out with a baseline
+pub fn ensure_prefix(plan: &LaunchPlan) -> RunnableResult<()> {
+    if plan.compat_dir.join("pfx").is_dir() {
+        return Ok(());
+    }
+    init_prefix(plan)
+}
@@ -0,0 +1,96 @@
+use std::fmt::Display;
+
+use crate::{
+    command::{
+        prefix::init::ensure_prefix,
+        run::{run_session, Run},
+        Runnable, RunnableResult,
+    },
+    paths::Paths,
+    proton::ProtonVersion,
+    steam::SteamData,
+};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "commandline", derive(clap::ValueEnum))]
+pub enum WineTool {
+    /// Wine configuration
+    Winecfg,
+    /// Registry editor
+    Regedit,
+    /// Command prompt
+    Cmd,
+    /// File explorer
+    Explorer,
+    /// Task manager
+    Taskmgr,
+    /// Add/remove programs
+    Uninstaller,
+    /// Control panel
+    Control,
+}
+
+impl WineTool {
+    /// The program to pass to `proton run`
+    pub fn program(&self) -> &'static str {
+        match self {
+            WineTool::Winecfg => "winecfg",
+            WineTool::Regedit => "regedit",
+            // cmd needs a console window to be of any use
+            WineTool::Cmd => "wineconsole",
+            WineTool::Explorer => "explorer",
+            WineTool::Taskmgr => "taskmgr",
+            WineTool::Uninstaller => "uninstaller",
+            WineTool::Control => "control",
+        }
+    }
+
+    pub fn args(&self) -> &'static [&'static str] {
+        match self {
+            WineTool::Cmd => &["cmd"],
+            _ => &[],
+        }
+    }
+}
+
+impl Display for WineTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Tool {
+    /// Save name of the game whose prefix to use
+    game: String,
+
+    /// The tool to run
+    tool: WineTool,
+
+    /// Optional proton version to use
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    proton: Option<ProtonVersion>,
+}
+
+impl Runnable for Tool {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        // The tool runs with the proton and game ids the game itself would get
+        let game = Run::game(&self.game, self.proton).plan(paths, steam_data)?;
+        println!(
+            "Launching {} for {} with {}",
+            self.tool, game.save_name, game.proton
+        );
+
+        std::fs::create_dir_all(&game.compat_dir)?;
+        let _lock = paths.lock_compat_dir(&game.compat_dir)?;
+        let run_dir = paths.run_dir(&game.save_name);
+        let mut plan = game.run_other(self.tool.program());
+        plan.run_dir = run_dir.clone();
+        plan.argv
+            .extend(self.tool.args().iter().map(|a| a.to_string()));
+        ensure_prefix(&plan)?;
+
+        run_session(&plan, &run_dir)
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::Write;
+
+/// Ask the user a question and return the answer, without the trailing newline
+pub fn ask(question: &str) -> std::io::Result<String> {
+    print!("{} ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer)? == 0 {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(answer.trim().to_string())
+}
+
+/// Let the user pick one of the options by number, asking again until the answer is valid
+pub fn choose(question: &str, options: &[String]) -> std::io::Result<usize> {
+    println!("{}", question);
+    for (i, option) in options.iter().enumerate() {
+        println!("  {}) {}", i + 1, option);
+    }
+    loop {
+        let answer = ask(&format!("[1-{}]:", options.len()))?;
+        match answer.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= options.len() => return Ok(n - 1),
+            _ => println!("Please enter a number between 1 and {}", options.len()),
+        }
+    }
+}
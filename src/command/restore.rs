@@ -1,35 +1,746 @@
 use std::{
-    fs::File,
-    path::PathBuf,
+    collections::BTreeMap,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
-use crate::{paths::Paths, steam::SteamData};
+use glob::Pattern;
 
-use super::{Runnable, RunnableResult};
+use crate::{
+    backups::{
+        format_local, read_backup, BackupEntry, BackupManifest, EntryKind, GAME_DIR, MANIFEST,
+        REGISTRY_DIR,
+    },
+    game::GameConfig,
+    hash::hash_file,
+    paths::Paths,
+    proton::prefix_version,
+    registry::{hive_of, RegistryFile},
+    repository::Repository,
+    steam::SteamData,
+};
+
+use super::{
+    backups::find_backup,
+    prompt::{ask, choose},
+    Runnable, RunnableError, RunnableResult,
+};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "commandline", derive(clap::Args))]
 pub struct Restore {
-    /// Path to the backup file
-    backup: PathBuf,
+    /// Save name of the game to restore
+    /// Only optional with `--file`, then the save name the backup was made with is used
+    #[cfg_attr(feature = "commandline", clap(required_unless_present = "file"))]
+    game: Option<String>,
+
+    /// Id of the backup to restore, as shown by `backups list`
+    /// The start of an id is enough if it is unique, if not specified the latest backup is restored
+    #[cfg_attr(feature = "commandline", clap(conflicts_with = "file"))]
+    backup: Option<String>,
+
+    /// Restore a backup file from somewhere else instead of one from the backups of the game
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    file: Option<PathBuf>,
 
-    /// Optional save name to use
-    /// If not specified, the backup file without the extension will be used
+    /// Optional path to the game exe
+    /// Only needed to find a local compat folder for a game that hasn't been added
     #[cfg_attr(feature = "commandline", clap(short, long))]
-    save_name: Option<String>,
+    exe: Option<PathBuf>,
+
+    /// Where the game is installed, for backups with saves from the game folder.
+    /// If not specified, the folder the backup was made from is used if the game is installed there,
+    /// otherwise it asks first
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    game_dir: Option<PathBuf>,
+
+    /// Only restore these paths, relative to the prefix like `drive_c/users`.
+    /// Globs like `**/*.sav` work too, can be given multiple times
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    only: Vec<String>,
+
+    /// Don't restore anything, list which files would be added, overwritten or left unchanged
+    #[cfg_attr(feature = "commandline", clap(long))]
+    dry_run: bool,
+
+    /// What to do with files that are newer in the prefix than in the backup, instead of asking
+    #[cfg_attr(feature = "commandline", clap(long))]
+    on_conflict: Option<Resolution>,
 }
 
 impl Runnable for Restore {
     fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let backup_path = match (&self.file, &self.game) {
+            (Some(file), _) => file.clone(),
+            (None, Some(game)) => find_backup(paths, game, self.backup.as_deref())?.path,
+            (None, None) => unreachable!("clap requires a game or a file"),
+        };
+        let manifest = BackupManifest::read(&backup_path)?;
         let save_name = self
-            .save_name
-            .as_deref()
-            .unwrap_or_else(|| self.backup.file_stem().unwrap().to_str().unwrap());
-        let global_compat_dir = paths.compat_dir(save_name);
-        let f = File::open(&self.backup).unwrap();
-        let d = zstd::Decoder::new(f).unwrap();
-        let mut archive = tar::Archive::new(d);
-        archive.unpack(&global_compat_dir).unwrap();
+            .game
+            .clone()
+            .or_else(|| manifest.as_ref().map(|m| m.save_name.clone()))
+            .unwrap_or_else(|| {
+                backup_path
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            });
+
+        // Only looked up here, a dry run must not create anything
+        let compat_dir = paths.compat_path(&save_name, self.exe.as_deref())?;
+        if let Some(manifest) = &manifest {
+            println!("Restoring {}", describe(manifest));
+            let current = prefix_version(&compat_dir);
+            if let (Some(theirs), Some(ours)) = (&manifest.proton, &current) {
+                if theirs != ours {
+                    println!(
+                        "Warning: the backup was made with proton {}, but the prefix was last used with {}",
+                        theirs, ours
+                    );
+                }
+            }
+        }
+
+        let game_dir = match &self.game_dir {
+            Some(game_dir) => Some(game_dir.clone()),
+            None => self.game_dir_from_backup(paths, &save_name, manifest.as_ref())?,
+        };
+        let dest = Destination {
+            compat_dir: compat_dir.clone(),
+            game_dir,
+        };
+        let repository = paths.existing_repository();
+        let plan = self.plan(&backup_path, &repository, &dest, manifest.as_ref())?;
+        if self.dry_run {
+            print_plan(&plan);
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&compat_dir)?;
+        let _lock = paths.lock_compat_dir(&compat_dir)?;
+        let mut actions = BTreeMap::new();
+        for (path, status) in &plan.files {
+            let action = match status {
+                FileStatus::Added | FileStatus::Overwritten => Resolution::Overwrite,
+                FileStatus::Unchanged => continue,
+                FileStatus::NewerInPrefix => self.resolve_conflict(path)?,
+            };
+            actions.insert(path.clone(), action);
+        }
+
+        if manifest.is_none() {
+            println!("Warning: the backup has no manifest, so its files can't be verified");
+        }
+
+        // Unpack and verify everything first, so a broken backup leaves the prefix alone
+        let staging = Staging::new(compat_dir.join(".restore"))?;
+        let layout = manifest.as_ref().map(|m| m.layout).unwrap_or_default();
+        read_backup(
+            &backup_path,
+            &repository,
+            |mut entry| -> RunnableResult<()> {
+                let path = check_entry(&entry)?;
+                if path == Path::new(MANIFEST) {
+                    return Ok(());
+                }
+                let path = layout.compat_path(&path);
+                if !actions.contains_key(&path) {
+                    return Ok(());
+                }
+                let staged = staging.0.join(&path);
+                std::fs::create_dir_all(staged.parent().unwrap())?;
+                entry.unpack(&staged)?;
+                if let Some(manifest) = &manifest {
+                    let expected = manifest.files.get(&path).map(|f| f.hash.as_str());
+                    if entry.kind == EntryKind::File
+                        && expected != Some(hash_file(&staged)?.as_str())
+                    {
+                        return Err(RunnableError::ChecksumMismatch(path));
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        for (path, action) in &actions {
+            // Registry keys are merged into the hive, everything else in it stays as it is
+            if let (Some(hive), Resolution::Overwrite) = (registry_hive(path), action) {
+                let target = dest.writable(path)?;
+                let backup = RegistryFile::load(&staging.0.join(path))?;
+                let mut current = match target.exists() {
+                    true => RegistryFile::load(&target)?,
+                    false => RegistryFile {
+                        header: backup.header.clone(),
+                        keys: Vec::new(),
+                    },
+                };
+                for key in registry_keys(manifest.as_ref(), hive, &backup) {
+                    current.replace_subtree(&key, &backup);
+                }
+                current.save(&target)?;
+                continue;
+            }
+            let target = match action {
+                Resolution::Keep => continue,
+                Resolution::Overwrite => dest.writable(path)?,
+                Resolution::KeepBoth => {
+                    let mut name = path.file_name().unwrap().to_os_string();
+                    name.push(".restored");
+                    dest.writable(&path.with_file_name(name))?
+                }
+            };
+            move_file(&staging.0.join(path), &target)?;
+        }
+        for path in &plan.deleted {
+            match std::fs::remove_file(dest.join(path)?) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        println!(
+            "Restored {} files",
+            actions.values().filter(|a| **a != Resolution::Keep).count()
+        );
         Ok(())
     }
 }
+
+/// What restoring a file would do to the prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileStatus {
+    Added,
+    Overwritten,
+    Unchanged,
+    /// The file in the prefix was changed after the backup was made
+    NewerInPrefix,
+}
+
+/// What to do with a file that is newer in the prefix than in the backup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "commandline", derive(clap::ValueEnum))]
+pub enum Resolution {
+    /// Keep the file in the prefix
+    Keep,
+    /// Replace it with the one from the backup
+    Overwrite,
+    /// Keep the file in the prefix and restore the one from the backup next to it as `<name>.restored`
+    KeepBoth,
+}
+
+/// Everything restoring a backup would change
+struct RestorePlan {
+    files: BTreeMap<PathBuf, FileStatus>,
+    deleted: Vec<PathBuf>,
+}
+
+impl Restore {
+    /// Whether a path in the backup, relative to the compat dir, was asked for
+    fn selected(&self, path: &Path) -> bool {
+        if self.only.is_empty() {
+            return true;
+        }
+        let in_prefix = path.strip_prefix("pfx").unwrap_or(path);
+        self.only.iter().any(|only| {
+            let pattern = Pattern::new(only).ok();
+            [path, in_prefix].into_iter().any(|p| {
+                p.starts_with(only) || pattern.as_ref().is_some_and(|pat| pat.matches_path(p))
+            })
+        })
+    }
+
+    fn plan(
+        &self,
+        backup: &Path,
+        repository: &Repository,
+        dest: &Destination,
+        manifest: Option<&BackupManifest>,
+    ) -> RunnableResult<RestorePlan> {
+        let mut files = BTreeMap::new();
+        let layout = manifest.map(|m| m.layout).unwrap_or_default();
+        read_backup(backup, repository, |mut entry| -> RunnableResult<()> {
+            let path = check_entry(&entry)?;
+            if path == Path::new(MANIFEST) || entry.kind == EntryKind::Dir {
+                return Ok(());
+            }
+            let path = layout.compat_path(&path);
+            if !self.selected(&path) {
+                return Ok(());
+            }
+            let target = dest.join(&path)?;
+            let status = if let Some(hive) = registry_hive(&path) {
+                // Only the keys in the backup are restored, so only they are compared
+                let mut text = String::new();
+                entry.read_to_string(&mut text)?;
+                let backup = RegistryFile::parse(&text)?;
+                let keys = registry_keys(manifest, hive, &backup);
+                if !target.exists() {
+                    FileStatus::Added
+                } else if RegistryFile::diff(&RegistryFile::load(&target)?.extract(&keys), &backup)
+                    .is_empty()
+                {
+                    FileStatus::Unchanged
+                } else {
+                    FileStatus::Overwritten
+                }
+            } else if !target.exists() {
+                FileStatus::Added
+            } else if manifest
+                .and_then(|m| m.files.get(&path))
+                .is_some_and(|f| hash_file(&target).is_ok_and(|h| h == f.hash))
+            {
+                FileStatus::Unchanged
+            } else if modified_secs(&target)? > entry.mtime {
+                FileStatus::NewerInPrefix
+            } else {
+                FileStatus::Overwritten
+            };
+            files.insert(path, status);
+            Ok(())
+        })?;
+        if let Some(manifest) = manifest {
+            let missing = manifest
+                .files
+                .keys()
+                .find(|p| self.selected(p) && !files.contains_key(*p));
+            if let Some(missing) = missing {
+                return Err(RunnableError::MissingFromBackup(missing.clone()));
+            }
+            if let Some(unsafe_path) = manifest.deleted.iter().find(|p| !is_plain(p)) {
+                return Err(RunnableError::UnsafeBackupEntry(unsafe_path.clone()));
+            }
+        }
+        let mut deleted = Vec::new();
+        for path in manifest.iter().flat_map(|m| &m.deleted) {
+            if self.selected(path) && dest.join(path)?.symlink_metadata().is_ok() {
+                deleted.push(path.clone());
+            }
+        }
+        Ok(RestorePlan { files, deleted })
+    }
+
+    /// The game dir the backup was made from, if it is where the game is installed here or the user agrees.
+    /// It is written in the backup, so a backup from someone else could point it anywhere.
+    fn game_dir_from_backup(
+        &self,
+        paths: &Paths,
+        save_name: &str,
+        manifest: Option<&BackupManifest>,
+    ) -> RunnableResult<Option<PathBuf>> {
+        let Some(manifest) = manifest else {
+            return Ok(None);
+        };
+        let Some(game_dir) = &manifest.game_dir else {
+            return Ok(None);
+        };
+        let has_game_files = manifest
+            .files
+            .keys()
+            .any(|p| p.starts_with(GAME_DIR) && self.selected(p));
+        if !has_game_files {
+            return Ok(None);
+        }
+        let exe = match &self.exe {
+            Some(exe) => std::fs::canonicalize(exe).ok(),
+            None => GameConfig::load(paths, save_name)?.exe,
+        };
+        if exe.is_some_and(|exe| exe.starts_with(game_dir)) {
+            return Ok(Some(game_dir.clone()));
+        }
+        let question = format!(
+            "The backup has files from the game folder {}, which is not where {} is installed here. Restore them there? [y/N]",
+            game_dir.display(),
+            save_name
+        );
+        match ask(&question) {
+            Ok(answer)
+                if answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes") =>
+            {
+                Ok(Some(game_dir.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn resolve_conflict(&self, path: &Path) -> std::io::Result<Resolution> {
+        if let Some(resolution) = self.on_conflict {
+            return Ok(resolution);
+        }
+        let options = [
+            "Keep the file in the prefix".to_string(),
+            "Overwrite it with the backup".to_string(),
+            "Keep both, the backup is restored next to it as .restored".to_string(),
+        ];
+        let question = format!(
+            "{} is newer in the prefix than in the backup, what should happen to it?",
+            path.display()
+        );
+        Ok(match choose(&question, &options)? {
+            0 => Resolution::Keep,
+            1 => Resolution::Overwrite,
+            _ => Resolution::KeepBoth,
+        })
+    }
+}
+
+fn print_plan(plan: &RestorePlan) {
+    let sections = [
+        ("Would add", FileStatus::Added),
+        ("Would overwrite", FileStatus::Overwritten),
+        (
+            "Newer in the prefix, would ask what to do",
+            FileStatus::NewerInPrefix,
+        ),
+        ("Would leave unchanged", FileStatus::Unchanged),
+    ];
+    for (title, status) in sections {
+        let paths: Vec<&PathBuf> = plan
+            .files
+            .iter()
+            .filter(|(_, s)| **s == status)
+            .map(|(p, _)| p)
+            .collect();
+        if !paths.is_empty() {
+            println!("{}:", title);
+            for path in paths {
+                println!("  {}", path.display());
+            }
+        }
+    }
+    if !plan.deleted.is_empty() {
+        println!("Would delete:");
+        for path in &plan.deleted {
+            println!("  {}", path.display());
+        }
+    }
+    if plan.files.is_empty() && plan.deleted.is_empty() {
+        println!("Nothing to restore");
+    }
+}
+
+/// The path of an entry in the archive, if it is a plain file or folder with a relative path.
+/// Saves are never links, and the entries after a link could be written through it to anywhere.
+fn check_entry(entry: &BackupEntry) -> RunnableResult<PathBuf> {
+    match &entry.kind {
+        EntryKind::File | EntryKind::Dir if is_plain(&entry.path) => Ok(entry.path.clone()),
+        _ => Err(RunnableError::UnsafeBackupEntry(entry.path.clone())),
+    }
+}
+
+/// Whether a path is relative and has no `..` in it
+pub(crate) fn is_plain(path: &Path) -> bool {
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// The hive a path in the backup holds registry keys for, like `user.reg` for `registry/user.reg`
+fn registry_hive(path: &Path) -> Option<&str> {
+    let hive = path.strip_prefix(REGISTRY_DIR).ok()?;
+    match hive.components().count() {
+        1 => hive.to_str(),
+        _ => None,
+    }
+}
+
+/// The keys of a hive a backup restores, relative to the root of the hive.
+/// Keys the manifest lists but the backup doesn't have didn't exist, restoring removes them.
+fn registry_keys(
+    manifest: Option<&BackupManifest>,
+    hive: &str,
+    backup: &RegistryFile,
+) -> Vec<String> {
+    let keys: Vec<String> = manifest
+        .iter()
+        .flat_map(|m| &m.registry)
+        .filter_map(|k| hive_of(k).filter(|(h, _)| *h == hive).map(|(_, k)| k))
+        .collect();
+    match keys.is_empty() {
+        true => backup.keys.iter().map(|k| k.path.clone()).collect(),
+        false => keys,
+    }
+}
+
+/// Where the files of a backup go
+struct Destination {
+    compat_dir: PathBuf,
+    game_dir: Option<PathBuf>,
+}
+
+impl Destination {
+    /// The folder a path in the archive is restored into, and the path relative to it.
+    /// Files from the game dir are in a folder of their own, everything else has to be in the prefix.
+    fn root(&self, path: &Path) -> RunnableResult<(PathBuf, PathBuf)> {
+        let (root, relative) = if let Some(hive) = registry_hive(path) {
+            (self.compat_dir.join("pfx"), PathBuf::from(hive))
+        } else if let Ok(relative) = path.strip_prefix(GAME_DIR) {
+            let game_dir = self.game_dir.as_ref().ok_or(RunnableError::NoGameDir)?;
+            (game_dir.clone(), relative.to_path_buf())
+        } else if let Ok(relative) = path.strip_prefix("pfx") {
+            (self.compat_dir.join("pfx"), relative.to_path_buf())
+        } else {
+            return Err(RunnableError::UnsafeBackupEntry(path.to_path_buf()));
+        };
+        if relative.file_name().is_none() {
+            return Err(RunnableError::UnsafeBackupEntry(path.to_path_buf()));
+        }
+        Ok((root, relative))
+    }
+
+    /// Where a path in the archive ends up.
+    /// None of the folders on the way there may be symlinks, like `dosdevices/z:` which points at `/`,
+    /// or restoring could write or delete anything on the system.
+    fn join(&self, path: &Path) -> RunnableResult<PathBuf> {
+        let (mut target, relative) = self.root(path)?;
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            target.push(component);
+            if components.peek().is_none() {
+                break;
+            }
+            match target.symlink_metadata() {
+                Ok(m) if m.file_type().is_symlink() => {
+                    return Err(RunnableError::UnsafeBackupEntry(path.to_path_buf()))
+                }
+                Ok(_) => {}
+                // Folders that don't exist yet are created by restoring, so they can't be links
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(target)
+    }
+
+    /// Where a path in the archive is written to, creating the folders it goes in.
+    /// Unlike for reading and deleting, the file itself can't be a symlink either.
+    fn writable(&self, path: &Path) -> RunnableResult<PathBuf> {
+        let (root, _) = self.root(path)?;
+        let target = self.join(path)?;
+        if target
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink())
+        {
+            return Err(RunnableError::UnsafeBackupEntry(path.to_path_buf()));
+        }
+        let parent = target.parent().unwrap();
+        std::fs::create_dir_all(parent)?;
+        // Checked again now the folders exist, in case one was swapped for a link in the meantime
+        if !std::fs::canonicalize(parent)?.starts_with(std::fs::canonicalize(&root)?) {
+            return Err(RunnableError::UnsafeBackupEntry(path.to_path_buf()));
+        }
+        Ok(target)
+    }
+}
+
+/// Move a file, copying it if the game dir is on another file system than the prefix
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// A directory to unpack into before moving the files into place, removed again when it is dropped.
+/// Being on the same file system as the prefix means moving is just a rename.
+pub struct Staging(pub PathBuf);
+
+impl Staging {
+    /// Start with an empty `path`, throwing away whatever an earlier attempt left there
+    pub fn new(path: PathBuf) -> std::io::Result<Self> {
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn modified_secs(path: &Path) -> std::io::Result<u64> {
+    Ok(path
+        .metadata()?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default())
+}
+
+/// One line saying where a backup came from
+pub fn describe(manifest: &BackupManifest) -> String {
+    let mut description = match &manifest.name {
+        Some(name) => format!("backup of {} ({})", name, manifest.save_name),
+        None => format!("backup of {}", manifest.save_name),
+    };
+    if let Some(created) = manifest.created() {
+        description.push_str(&format!(" from {}", format_local(created)));
+    }
+    if !manifest.host.is_empty() {
+        description.push_str(&format!(" made on {}", manifest.host));
+    }
+    if let Some(proton) = &manifest.proton {
+        description.push_str(&format!(" with proton {}", proton));
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write};
+
+    use super::*;
+    use crate::repository::Repository;
+
+    /// Write a tar.zst with entries of the given names, types and link targets,
+    /// bypassing the checks the tar crate does on names
+    fn write_tar(path: &Path, entries: &[(&str, tar::EntryType, Option<&str>)]) {
+        let encoder = zstd::Encoder::new(File::create(path).unwrap(), 3).unwrap();
+        let mut builder = tar::Builder::new(encoder);
+        for (name, entry_type, link) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(*entry_type);
+            header.set_mode(0o644);
+            if let Some(link) = link {
+                header.set_link_name(link).unwrap();
+            }
+            let data: &[u8] = match entry_type.is_file() {
+                true => b"save",
+                false => b"",
+            };
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    /// What `check_entry` says about every entry of a backup, the path if it is accepted
+    fn checked(archive: &Path) -> Vec<Option<PathBuf>> {
+        let mut checked = Vec::new();
+        let repository = Repository::new(PathBuf::new());
+        read_backup(archive, &repository, |entry| -> RunnableResult<()> {
+            checked.push(check_entry(&entry).ok());
+            Ok(())
+        })
+        .unwrap();
+        checked
+    }
+
+    #[test]
+    fn accepts_plain_files_and_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("ok.backup");
+        write_tar(
+            &archive,
+            &[
+                ("manifest.json", tar::EntryType::Regular, None),
+                ("pfx/drive_c/", tar::EntryType::Directory, None),
+                ("pfx/drive_c/save.dat", tar::EntryType::Regular, None),
+                ("./game/1.sav", tar::EntryType::Regular, None),
+            ],
+        );
+        assert_eq!(
+            checked(&archive),
+            [
+                Some(PathBuf::from("manifest.json")),
+                Some(PathBuf::from("pfx/drive_c")),
+                Some(PathBuf::from("pfx/drive_c/save.dat")),
+                Some(PathBuf::from("./game/1.sav")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_links_and_paths_out_of_the_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("evil.backup");
+        write_tar(
+            &archive,
+            &[
+                ("pfx/../../escape", tar::EntryType::Regular, None),
+                ("/etc/passwd", tar::EntryType::Regular, None),
+                ("pfx/drive_c/link", tar::EntryType::Symlink, Some("/etc")),
+                ("pfx/drive_c/rel", tar::EntryType::Symlink, Some("save")),
+                (
+                    "pfx/drive_c/hard",
+                    tar::EntryType::Link,
+                    Some("pfx/user.reg"),
+                ),
+                ("pfx/drive_c/fifo", tar::EntryType::Fifo, None),
+            ],
+        );
+        assert_eq!(checked(&archive), [None, None, None, None, None, None]);
+    }
+
+    #[test]
+    fn rejects_symlinks_in_zips() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("evil.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.start_file("pfx/drive_c/save.dat", options).unwrap();
+        zip.write_all(b"save").unwrap();
+        zip.add_symlink("pfx/drive_c/link", "/etc", options)
+            .unwrap();
+        zip.finish().unwrap();
+        assert_eq!(
+            checked(&archive),
+            [Some(PathBuf::from("pfx/drive_c/save.dat")), None]
+        );
+    }
+
+    #[test]
+    fn destinations_stay_in_the_prefix_and_game_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let compat_dir = dir.path().join("compat");
+        let game_dir = dir.path().join("game");
+        std::fs::create_dir_all(compat_dir.join("pfx/dosdevices")).unwrap();
+        std::fs::create_dir_all(&game_dir).unwrap();
+        std::os::unix::fs::symlink("/", compat_dir.join("pfx/dosdevices/z:")).unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", compat_dir.join("pfx/passwd")).unwrap();
+        let dest = Destination {
+            compat_dir: compat_dir.clone(),
+            game_dir: None,
+        };
+        let join = |path: &str| dest.join(Path::new(path));
+        let writable = |path: &str| dest.writable(Path::new(path));
+
+        assert_eq!(
+            writable("pfx/drive_c/users/steamuser/save.dat").unwrap(),
+            compat_dir.join("pfx/drive_c/users/steamuser/save.dat")
+        );
+        assert!(compat_dir.join("pfx/drive_c/users/steamuser").is_dir());
+        assert_eq!(
+            join("registry/user.reg").unwrap(),
+            compat_dir.join("pfx/user.reg")
+        );
+        for unsafe_path in [
+            "pfx/dosdevices/z:/etc/passwd",
+            "pfx",
+            "baseline.json",
+            "registry/sub/user.reg",
+        ] {
+            assert!(join(unsafe_path).is_err(), "{}", unsafe_path);
+        }
+        // The link itself can be deleted, but not written through
+        assert!(join("pfx/passwd").is_ok());
+        assert!(writable("pfx/passwd").is_err());
+        assert!(matches!(join("game/1.sav"), Err(RunnableError::NoGameDir)));
+
+        let dest = Destination {
+            compat_dir,
+            game_dir: Some(game_dir.clone()),
+        };
+        assert_eq!(
+            dest.writable(Path::new("game/saves/1.sav")).unwrap(),
+            game_dir.join("saves/1.sav")
+        );
+    }
+}
@@ -1,40 +1,92 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-use crate::{paths::Paths, proton::ProtonVersion, steam::SteamData};
+use crate::{
+    game::{save_name_for, GameConfig},
+    launch::LaunchPlan,
+    lnk::{is_link, ShellLink},
+    paths::Paths,
+    pe::{ExeInfo, Suggestion},
+    proton::{wine_bin_dir, ProtonVersion},
+    session::Session,
+    steam::SteamData,
+};
 
-use super::{Runnable, RunnableError, RunnableResult};
+use super::{
+    backup::{Backup, BackupOutcome},
+    prefix::init::ensure_prefix,
+    Runnable, RunnableError, RunnableResult,
+};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "commandline", derive(clap::Args))]
 pub struct Run {
-    /// Optional path to the exe of the game
+    /// Optional path to the exe of the game, a `.lnk` shortcut to it, or the name of an added game
     /// If not specified, the first part of the [ARGS] will be used as the exe
-    exe: Option<PathBuf>,
+    pub exe: Option<PathBuf>,
 
     /// Args to pass to the game directly
     /// If the exe is not specified, the first part of the [ARGS] will be used as the exe
     /// The rest of the [ARGS] will be passed to the game
     #[cfg_attr(feature = "commandline", clap(last = true))]
-    args: Vec<String>,
+    pub args: Vec<String>,
 
     /// Optional save name to use
-    /// If not specified, the game exe without the extension will be used
+    /// If not specified, it is picked like `game add` does, from the product name of the exe or its file name
     #[cfg_attr(feature = "commandline", clap(short, long))]
-    save_name: Option<String>,
+    pub save_name: Option<String>,
+
+    /// Which exe of an added game to launch, like a launcher or settings tool
+    /// If not specified, the main exe of the game will be launched
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    pub target: Option<String>,
 
     /// Optional proton version to use
     #[cfg_attr(feature = "commandline", clap(short, long))]
-    proton: Option<ProtonVersion>,
+    pub proton: Option<ProtonVersion>,
 
     /// Run the game in the same directory as the exe.
     /// Some games need this since they use relative paths, this includes some Unity games.
     /// This does require write access to the game directory, since a dxvk cache will be created there.
     ///
     #[cfg_attr(feature = "commandline", clap(long))]
-    here: bool,
+    pub here: bool,
+
+    /// Command to wrap proton in, like `gamemoderun` or `mangohud`.
+    /// Can be given multiple times, the first one is the outermost
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    pub wrapper: Vec<String>,
+
+    /// Don't launch anything, print what would be launched instead
+    #[cfg_attr(feature = "commandline", clap(long))]
+    pub dry_run: bool,
+
+    /// Print the dry run as JSON instead of a shell command
+    #[cfg_attr(feature = "commandline", clap(long, requires = "dry_run"))]
+    pub json: bool,
 }
 
 impl Run {
+    /// Run an added game by its save name, with nothing else given on the command line
+    pub fn game(save_name: &str, proton: Option<ProtonVersion>) -> Self {
+        Self {
+            exe: Some(PathBuf::from(save_name)),
+            args: Vec::new(),
+            save_name: None,
+            target: None,
+            proton,
+            here: false,
+            wrapper: Vec::new(),
+            dry_run: false,
+            json: false,
+        }
+    }
+
     fn get_exe_and_args(&self) -> Result<(PathBuf, &[String]), RunnableError> {
         if let Some(exe) = &self.exe {
             Ok((exe.clone(), &self.args))
@@ -46,50 +98,303 @@ impl Run {
             }
         }
     }
+
+    /// Figure out what to launch.
+    /// The exe can be the name of an added game or a shortcut, both of which can bring args,
+    /// a working dir and a compat dir of their own.
+    fn get_target(&self, paths: &Paths) -> RunnableResult<Target> {
+        let (exe, args) = self.get_exe_and_args()?;
+        let mut target = Target {
+            exe,
+            args: args.to_vec(),
+            save_name: self.save_name.clone(),
+            working_dir: None,
+            compat_dir: None,
+            proton: self.proton,
+            here: self.here,
+            game_env: BTreeMap::new(),
+        };
+
+        // An added game can be run by its save name or by the path to its exe
+        let name = target.exe.to_string_lossy().to_string();
+        let mut main_exe = None;
+        let added = if GameConfig::is_added(paths, &target.exe) {
+            Some(name.clone())
+        } else {
+            GameConfig::find_by_exe(paths, &target.exe)
+        };
+        if let Some(name) = added {
+            let config = GameConfig::load(paths, &name)?;
+            match config.target(self.target.as_deref()) {
+                Some(launch) => {
+                    main_exe = config.exe.clone();
+                    target.exe = launch.exe;
+                    target.args.splice(0..0, launch.args);
+                    target.working_dir = launch.working_dir;
+                    target.proton = target.proton.or(config.proton);
+                    target.here |= config.here;
+                    target.game_env = config.game_id_env();
+                    target.save_name.get_or_insert(name);
+                }
+                None => {
+                    if let Some(t) = &self.target {
+                        return Err(RunnableError::UnknownTarget(name, t.clone()));
+                    }
+                }
+            }
+        } else if self.target.is_some() {
+            return Err(RunnableError::UnknownGame(name));
+        }
+
+        // Named after the exe or shortcut that was passed, not what a shortcut points to, like `game add` does
+        let save_name = target
+            .save_name
+            .get_or_insert_with(|| save_name_for(paths, &target.exe))
+            .clone();
+
+        if is_link(&target.exe) {
+            let link_path = target.exe.clone();
+            let prefix = paths.compat_path(&save_name, None)?.join("pfx");
+            let resolved = ShellLink::resolve_file(&link_path, || prefix)?;
+            target.exe = resolved.exe;
+            target.args.splice(0..0, resolved.args);
+            target.working_dir = resolved.working_dir.or(target.working_dir);
+            target.compat_dir = resolved.compat_dir;
+        }
+        // Every target of a game shares the compat dir of its main exe, also when that is the local one next to it
+        if target.compat_dir.is_none() {
+            let exe = main_exe.as_ref().unwrap_or(&target.exe);
+            target.compat_dir = Some(paths.compat_path(&save_name, Some(exe))?);
+        }
+
+        Ok(target)
+    }
+}
+
+/// The exe to launch, with everything that was resolved along with it
+struct Target {
+    exe: PathBuf,
+    args: Vec<String>,
+    save_name: Option<String>,
+    working_dir: Option<PathBuf>,
+    compat_dir: Option<PathBuf>,
+    proton: Option<ProtonVersion>,
+    here: bool,
+    /// Steam or umu ids of an added game
+    game_env: BTreeMap<String, String>,
+}
+
+impl Run {
+    /// Resolve everything needed to launch the game, without launching it
+    pub fn plan(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<LaunchPlan> {
+        let Target {
+            exe,
+            args,
+            save_name,
+            working_dir,
+            compat_dir,
+            proton,
+            here,
+            game_env,
+        } = self.get_target(paths)?;
+
+        let (selected, proton_path) = select_proton(proton, steam_data)?;
+        let save_name = save_name.expect("get_target always picks a save name");
+
+        // Nothing is created here, a dry run only prints the plan
+        let compat_dir = compat_dir.expect("get_target always picks a compat dir");
+        let run_dir = if here {
+            exe.parent().unwrap().to_path_buf()
+        } else if let Some(working_dir) = working_dir {
+            working_dir
+        } else {
+            paths.run_path(&save_name)
+        };
+
+        let mut plan = LaunchPlan::new(
+            selected,
+            proton_path,
+            steam_data,
+            save_name,
+            compat_dir,
+            run_dir,
+            exe,
+        );
+        plan.argv.extend(args);
+        plan.env.extend(game_env);
+        plan.wrap(&self.wrapper);
+        Ok(plan)
+    }
 }
 
 impl Runnable for Run {
     fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
-        let selected_proton = self.proton.filter(|p| p.is_installed(steam_data));
-        if let Some(handpicked) = self.proton {
-            if selected_proton.is_none() {
-                return Err(RunnableError::SelectedProtonNotInstalled(handpicked));
+        let plan = self.plan(paths, steam_data)?;
+        if self.dry_run {
+            if self.json {
+                println!("{}", plan.to_json());
+            } else {
+                println!("{}", plan.to_shell());
             }
+            return Ok(());
         }
-        let selected_proton = selected_proton.or_else(|| ProtonVersion::best_installed(steam_data));
 
-        let (exe, args) = self.get_exe_and_args()?;
+        println!("Launching {} with {}", plan.exe.display(), plan.proton);
+        print_hints(&plan, paths);
+        std::fs::create_dir_all(&plan.compat_dir)?;
+        std::fs::create_dir_all(&plan.run_dir)?;
+        let lock = paths.lock_compat_dir(&plan.compat_dir)?;
+        ensure_prefix(&plan)?;
 
-        if let Some(selected) = selected_proton {
-            let save_name = self
-                .save_name
-                .as_deref()
-                .unwrap_or_else(|| exe.file_stem().unwrap().to_str().unwrap());
-            let proton_path = selected.get_path(steam_data).expect("You somehow managed to delete the selected proton version while running this command");
-            let proton_command = proton_path.join("proton");
+        let started = Instant::now();
+        let res = run_session(&plan, &paths.run_dir(&plan.save_name));
 
-            println!("Launching {} with {}", exe.display(), selected);
+        println!("Session summary for {}:", plan.save_name);
+        println!("  Played for {}", format_duration(started.elapsed()));
+        let config = GameConfig::load(paths, &plan.save_name).unwrap_or_default();
+        if config.auto_backup {
+            // The game is gone, but wine might still be writing to the prefix
+            let waited = Command::new(wine_bin_dir(&plan.proton_path).join("wineserver"))
+                .arg("-w")
+                .env("WINEPREFIX", plan.compat_dir.join("pfx"))
+                .status();
+            if let Err(e) = waited {
+                println!("  Could not wait for wine to finish: {}", e);
+            }
+            // Still holding the lock, so nothing can start in the prefix while it is backed up
+            match Backup::game(&plan.save_name).create(paths, true, Some(&lock)) {
+                Ok(BackupOutcome::Created { path, removed }) => {
+                    println!("  Created backup {}", path.display());
+                    for removed in removed {
+                        println!(
+                            "  Removed backup {} from {}",
+                            removed.id,
+                            removed.local_time()
+                        );
+                    }
+                }
+                Ok(BackupOutcome::Unchanged) => {
+                    println!("  Skipped the backup, nothing changed since the last one")
+                }
+                Err(e) => println!("  Backup failed: {}", e),
+            }
+        }
+        res
+    }
+}
 
-            let compat_dir = paths.compat_dir(save_name);
-            let run_dir = if self.here {
-                exe.parent().unwrap().to_path_buf()
-            } else {
-                paths.run_dir(save_name)
-            };
-
-            let mut command = std::process::Command::new(proton_command);
-            command.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", &steam_data.path);
-            command.env("STEAM_COMPAT_DATA_PATH", compat_dir);
-            command.current_dir(run_dir);
-            command.arg("run");
-            command.arg(exe);
-            command.args(args);
-
-            let res = command.spawn().map_err(RunnableError::SpawnError)?.wait()?;
-            println!("Exited with status {}", res);
-            Ok(())
+/// A duration like `1h 02m` or `3m 12s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Tell the user about settings that might help this game, if they aren't used already
+fn print_hints(plan: &LaunchPlan, paths: &Paths) {
+    let Ok(info) = ExeInfo::inspect(&plan.exe) else {
+        return;
+    };
+    let applied_verbs = GameConfig::load(paths, &plan.save_name)
+        .map(|c| c.winetricks)
+        .unwrap_or_default();
+    for suggestion in info.suggestions() {
+        let applied = match &suggestion {
+            Suggestion::RunHere => Some(plan.run_dir.as_path()) == plan.exe.parent(),
+            Suggestion::Arg(arg) => plan.argv.contains(arg),
+            Suggestion::Winetricks(verb) => applied_verbs.contains(verb),
+        };
+        if !applied {
+            println!("Hint: if the game has trouble starting, {}", suggestion);
+        }
+    }
+}
+
+/// Pick the proton version to use.
+/// This is the handpicked one if it is installed, otherwise the best installed version.
+/// Returns it along with the folder it is installed in.
+pub fn select_proton(
+    handpicked: Option<ProtonVersion>,
+    steam_data: &SteamData,
+) -> RunnableResult<(ProtonVersion, PathBuf)> {
+    let selected = if let Some(handpicked) = handpicked {
+        if handpicked.is_installed(steam_data) {
+            handpicked
         } else {
-            Err(RunnableError::NoProtonAtAll)
+            return Err(RunnableError::SelectedProtonNotInstalled(handpicked));
+        }
+    } else {
+        ProtonVersion::best_installed(steam_data).ok_or(RunnableError::NoProtonAtAll)?
+    };
+    // Steam can list it as installed while its folder is gone, like after deleting it by hand
+    let path = selected
+        .get_path(steam_data)
+        .filter(|path| path.join("proton").is_file())
+        .ok_or(RunnableError::ProtonNotFound(selected))?;
+    Ok((selected, path))
+}
+
+/// Launch a plan, recording it as the running session in `session_dir` until it exits
+pub fn run_session(plan: &LaunchPlan, session_dir: &Path) -> RunnableResult<()> {
+    let mut child = plan.command().spawn().map_err(RunnableError::SpawnError)?;
+    Session::new(
+        child.id(),
+        plan.proton_path.clone(),
+        plan.compat_dir.clone(),
+    )
+    .save(session_dir)?;
+    let res = child.wait();
+    Session::remove(session_dir);
+    let res = res?;
+    println!("Exited with status {}", res);
+    if res.success() {
+        Ok(())
+    } else {
+        Err(RunnableError::GameExited(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::game::LaunchTarget;
+
+    #[test]
+    fn targets_share_the_compat_dir_of_the_main_exe() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_dir = dir.path().join("Game");
+        std::fs::create_dir_all(game_dir.join("tools")).unwrap();
+        std::fs::write(game_dir.join("Game.exe"), b"MZ").unwrap();
+        std::fs::write(game_dir.join("tools/launcher.exe"), b"MZ").unwrap();
+        let mut config = GameConfig {
+            exe: Some(game_dir.join("Game.exe")),
+            ..Default::default()
+        };
+        config.targets.insert(
+            "launcher".to_string(),
+            LaunchTarget {
+                exe: game_dir.join("tools/launcher.exe"),
+                args: Vec::new(),
+                working_dir: None,
+            },
+        );
+
+        let run = Run {
+            target: Some("launcher".to_string()),
+            ..Run::game("game", None)
+        };
+        for (local_game, local_flag) in [(true, false), (false, true)] {
+            let paths = Paths::in_dir(dir.path(), local_flag);
+            config.local = local_game;
+            config.save(&paths, "game").unwrap();
+
+            let target = run.get_target(&paths).unwrap();
+            assert_eq!(target.exe, game_dir.join("tools/launcher.exe"));
+            assert_eq!(target.compat_dir, Some(game_dir.join("compat")));
         }
     }
 }
@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::{paths::Paths, proton::ProtonVersion, steam::SteamData};
+use crate::{
+    lnk::LnkError, lock::LockError, ludusavi::LudusaviError, paths::Paths, pe::PeError,
+    proton::ProtonVersion, registry::RegistryError, steam::SteamData,
+};
 
 #[derive(Debug, Error)]
 pub enum RunnableError {
@@ -8,6 +11,8 @@ pub enum RunnableError {
     NoProtonAtAll,
     #[error("{} is not installed, you can install it with `proton-launch install {}`", .0, .0.arg_name())]
     SelectedProtonNotInstalled(ProtonVersion),
+    #[error("The folder of {} is missing, install it again with `proton-launch install {}`", .0, .0.arg_name())]
+    ProtonNotFound(ProtonVersion),
 
     #[error("Failed to spawn process: {}", .0)]
     SpawnError(std::io::Error),
@@ -17,6 +22,120 @@ pub enum RunnableError {
 
     #[error("No executable specified")]
     NoExe,
+
+    #[error("Game exited with {}", .0)]
+    GameExited(std::process::ExitStatus),
+
+    #[error("{} is not running", .0)]
+    NotRunning(String),
+
+    #[error("{}", .0)]
+    Locked(#[from] LockError),
+
+    #[error("No winetricks verbs specified")]
+    NoVerbs,
+
+    #[error("Winetricks failed to apply {}, it exited with {}", .0.join(" "), .1)]
+    WinetricksFailed(Vec<String>, std::process::ExitStatus),
+
+    #[error("{} has not been added, add it with `proton-launch game add`", .0)]
+    UnknownGame(String),
+
+    #[error("{} has no target called {}, add it with `proton-launch game add-target`", .0, .1)]
+    UnknownTarget(String, String),
+
+    #[error("Could not read shortcut: {}", .0)]
+    Lnk(#[from] LnkError),
+
+    #[error("The installer did not create any executables or shortcuts")]
+    NothingInstalled,
+
+    #[error("{}", .0)]
+    Pe(#[from] PeError),
+
+    #[error("Creating the prefix failed, wineboot exited with {}", .0)]
+    PrefixInit(std::process::ExitStatus),
+
+    #[error("{} has no baseline to compare against, create one with `proton-launch prefix init`", .0)]
+    NoBaseline(String),
+
+    #[error("No single backup of {} matches {}, see `proton-launch backups list`", .0, .1)]
+    UnknownBackup(String, String),
+
+    #[error("The backup contains {}, which would end up outside the prefix", .0.display())]
+    UnsafeBackupEntry(std::path::PathBuf),
+
+    #[error("{} in the backup does not match its checksum, the backup is damaged", .0.display())]
+    ChecksumMismatch(std::path::PathBuf),
+
+    #[error("{} is listed in the manifest of the backup but missing from it", .0.display())]
+    MissingFromBackup(std::path::PathBuf),
+
+    #[error("{}", .0)]
+    Ludusavi(#[from] LudusaviError),
+
+    #[error("{} is not in the ludusavi manifest, link it to its name there with `proton-launch game link --ludusavi`", .0)]
+    NotInLudusavi(String),
+
+    #[error("There is no config dir to find the ludusavi manifest in, pass it with `--ludusavi-manifest`")]
+    NoLudusaviManifest,
+
+    #[error("{} can't be backed up, only keys of HKEY_CURRENT_USER and HKEY_LOCAL_MACHINE are in the prefix", .0)]
+    NotInHive(String),
+
+    #[error("The backup has files from the game folder, pass it with `--game-dir`")]
+    NoGameDir,
+
+    #[error("No installed Steam app matches {}, pass its app id with `--app-id`", .0)]
+    NoSteamMatch(String),
+
+    #[error("{} has no prefix yet, create it with `proton-launch prefix init`", .0)]
+    NoPrefix(String),
+
+    #[error("{} already has a prefix in {}, pass --force to replace it", .0, .1.display())]
+    PrefixExists(String, std::path::PathBuf),
+
+    #[error("{} is not a prefix exported with `proton-launch prefix export`", .0.display())]
+    NotAPrefixArchive(std::path::PathBuf),
+
+    #[error("Found {} problems in the backup repository", .0)]
+    RepositoryDamaged(usize),
+
+    #[error("{}", .0)]
+    Registry(#[from] RegistryError),
+}
+
+/// Exit code for errors of the launcher itself, the same one `env` and `timeout` use,
+/// so scripts can tell them apart from a game that exited with 1
+pub const LAUNCHER_FAILED: i32 = 125;
+
+/// Exit code when the game (or wine, or a tool) could not be started at all
+pub const SPAWN_FAILED: i32 = 126;
+
+impl RunnableError {
+    /// The code the process should exit with for this error.
+    /// If the game itself failed this is its exit code,
+    /// or 128 + the signal number if it was killed, like a shell would report it.
+    /// If it couldn't be started it is [`SPAWN_FAILED`], and every other error is [`LAUNCHER_FAILED`].
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunnableError::GameExited(status) => {
+                if let Some(code) = status.code() {
+                    return code;
+                }
+                #[cfg(unix)]
+                {
+                    use std::os::unix::process::ExitStatusExt;
+                    if let Some(signal) = status.signal() {
+                        return 128 + signal;
+                    }
+                }
+                LAUNCHER_FAILED
+            }
+            RunnableError::SpawnError(_) => SPAWN_FAILED,
+            _ => LAUNCHER_FAILED,
+        }
+    }
 }
 
 pub type RunnableResult<O> = Result<O, RunnableError>;
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use crate::{paths::Paths, session::Session, steam::SteamData};
+
+use super::{Runnable, RunnableError, RunnableResult};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Stop {
+    /// Save name of the game to stop
+    game: String,
+
+    /// Seconds to wait for the game to exit before killing it
+    #[cfg_attr(feature = "commandline", clap(short, long, default_value_t = 10))]
+    timeout: u64,
+}
+
+impl Runnable for Stop {
+    fn run(&self, paths: &Paths, _steam_data: &SteamData) -> RunnableResult<()> {
+        let run_dir = paths.run_dir(&self.game);
+        let session =
+            Session::load(&run_dir).ok_or_else(|| RunnableError::NotRunning(self.game.clone()))?;
+        let timeout = Duration::from_secs(self.timeout);
+
+        println!("Asking {} (pid {}) to stop", self.game, session.pid);
+        if let Err(e) = session.ask_to_stop() {
+            eprintln!("Could not run wineserver: {}", e);
+        }
+        if !session.wait_for_exit(timeout) {
+            println!("Still running after {}s, sending SIGTERM", self.timeout);
+            session.signal("TERM")?;
+            if !session.wait_for_exit(timeout) {
+                println!("Still running, sending SIGKILL");
+                session.signal("KILL")?;
+                session.wait_for_exit(timeout);
+            }
+        }
+        Session::remove(&run_dir);
+        println!("Stopped {}", self.game);
+        Ok(())
+    }
+}
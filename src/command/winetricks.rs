@@ -0,0 +1,86 @@
+use std::process::Command;
+
+use crate::{
+    game::GameConfig,
+    paths::Paths,
+    proton::{wine_bin_dir, ProtonVersion},
+    steam::SteamData,
+};
+
+use super::{prefix::init::ensure_prefix, run::Run, Runnable, RunnableError, RunnableResult};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "commandline", derive(clap::Args))]
+pub struct Winetricks {
+    /// Save name of the game whose prefix to use
+    game: String,
+
+    /// Winetricks verbs to apply, like `vcrun2019` or `d3dcompiler_47`
+    verbs: Vec<String>,
+
+    /// Apply all verbs that were applied to this game before again,
+    /// useful after the prefix has been reset
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    reapply: bool,
+
+    /// Don't show winetricks dialogs, install everything unattended
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    quiet: bool,
+
+    /// Optional proton version to use
+    #[cfg_attr(feature = "commandline", clap(short, long))]
+    proton: Option<ProtonVersion>,
+}
+
+impl Runnable for Winetricks {
+    fn run(&self, paths: &Paths, steam_data: &SteamData) -> RunnableResult<()> {
+        let mut config = GameConfig::load(paths, &self.game)?;
+        let mut verbs = if self.reapply {
+            config.winetricks.clone()
+        } else {
+            Vec::new()
+        };
+        verbs.extend(self.verbs.iter().cloned());
+        if verbs.is_empty() {
+            return Err(RunnableError::NoVerbs);
+        }
+
+        // Winetricks gets the proton and game ids the game itself would run with
+        let plan = Run::game(&self.game, self.proton).plan(paths, steam_data)?;
+        let bin_dir = wine_bin_dir(&plan.proton_path);
+
+        std::fs::create_dir_all(&plan.compat_dir)?;
+        std::fs::create_dir_all(&plan.run_dir)?;
+        let _lock = paths.lock_compat_dir(&plan.compat_dir)?;
+        ensure_prefix(&plan)?;
+
+        println!(
+            "Running winetricks {} with {}",
+            verbs.join(" "),
+            plan.proton
+        );
+        let mut command = Command::new("winetricks");
+        command.envs(&plan.env);
+        command.env("WINEPREFIX", plan.compat_dir.join("pfx"));
+        command.env("WINE", bin_dir.join("wine"));
+        command.env("WINESERVER", bin_dir.join("wineserver"));
+        if self.quiet {
+            command.arg("-q");
+        }
+        command.args(&verbs);
+
+        let res = command.spawn().map_err(RunnableError::SpawnError)?.wait()?;
+        println!("Exited with status {}", res);
+        if !res.success() {
+            return Err(RunnableError::WinetricksFailed(verbs, res));
+        }
+
+        for verb in verbs {
+            if !config.winetricks.contains(&verb) {
+                config.winetricks.push(verb);
+            }
+        }
+        config.save(paths, &self.game)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{backups::Retention, lnk::is_link, paths::Paths, pe::ExeInfo, proton::ProtonVersion};
+
+/// Settings for a single game, stored in the config dir by save name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// Name to show for the game, like in desktop entries
+    pub name: Option<String>,
+    /// Path to the game exe
+    pub exe: Option<PathBuf>,
+    /// Keep the compat folder next to the exe instead of in the data dir
+    pub local: bool,
+    /// Use this compat folder instead of the global or local one,
+    /// for games that were installed into a prefix
+    pub compat_dir: Option<PathBuf>,
+    /// Args to always pass to the game, before any args given on the command line
+    pub args: Vec<String>,
+    /// Directory to run the game in, instead of the run dir
+    pub working_dir: Option<PathBuf>,
+    /// Proton version to run the game with, if not specified on the command line
+    pub proton: Option<ProtonVersion>,
+    /// Run the game in the directory of the exe, like `run --here`
+    pub here: bool,
+    /// Winetricks verbs that have been applied to the prefix, in order
+    pub winetricks: Vec<String>,
+    /// Steam app id of the game, proton and protonfixes use this to apply fixes for the game
+    pub steam_app_id: Option<u64>,
+    /// umu game id like `umu-1234`, protonfixes uses this for games from stores other than Steam
+    pub umu_id: Option<String>,
+    /// Store the game is from for umu, like `gog` or `egs`
+    pub store: Option<String>,
+    /// Name of the game in the ludusavi manifest, backups of linked games contain the saves it lists
+    pub ludusavi: Option<String>,
+    /// Registry keys to back up, like `HKEY_CURRENT_USER/Software/Vendor/Game`, on top of what the ludusavi manifest lists
+    pub registry_keys: Vec<String>,
+    /// Back up the saves every time the game exits, see `proton-launch backups auto`
+    pub auto_backup: bool,
+    /// Store backups in the deduplicated repository instead of as archives
+    pub repository: bool,
+    /// Which backups to keep when a new one is made
+    pub retention: Retention,
+    /// Other exes of the game by name, like a launcher, settings tool or dedicated server
+    pub targets: BTreeMap<String, LaunchTarget>,
+}
+
+/// The save name an exe or shortcut gets if none is given.
+/// Shortcut names are usually fine already, exe names often aren't,
+/// so for exes it is the product name from the version info if they have one.
+/// Every command picks the save name this way, so the same exe always gets the same prefix and backups.
+/// Exes used to be named after their file only, so one that already has a prefix, backups or a config
+/// under that name keeps it.
+pub fn save_name_for(paths: &Paths, exe: &Path) -> String {
+    let stem = exe.file_stem().unwrap().to_string_lossy().to_string();
+    if is_link(exe) || paths.is_used(&stem) {
+        return stem;
+    }
+    ExeInfo::inspect(exe)
+        .ok()
+        .and_then(|i| i.save_name())
+        .unwrap_or(stem)
+}
+
+/// An exe of a game that shares its compat folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchTarget {
+    pub exe: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+}
+
+impl GameConfig {
+    /// Load the config of a game, or the default config if there is none yet
+    pub fn load(paths: &Paths, save_name: &str) -> std::io::Result<Self> {
+        let path = paths.game_config(save_name);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("could not parse {}: {}", path.display(), e),
+                )
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether a game with this save name has been added
+    pub fn exists(paths: &Paths, save_name: &str) -> bool {
+        paths.game_config(save_name).is_file()
+    }
+
+    /// Whether what was passed as the exe is the save name of an added game instead.
+    /// Only a bare name that isn't a file can be one.
+    pub fn is_added(paths: &Paths, exe: &Path) -> bool {
+        !exe.is_file()
+            && exe.components().count() == 1
+            && Self::exists(paths, &exe.to_string_lossy())
+    }
+
+    /// The exe to launch for a target, or the main exe of the game if no target is given
+    pub fn target(&self, target: Option<&str>) -> Option<LaunchTarget> {
+        match target {
+            Some(target) => self.targets.get(target).cloned(),
+            None => Some(LaunchTarget {
+                exe: self.exe.clone()?,
+                args: self.args.clone(),
+                working_dir: self.working_dir.clone(),
+            }),
+        }
+    }
+
+    /// Environment variables that tell proton which game this is, so it can apply its fixes
+    pub fn game_id_env(&self) -> BTreeMap<String, String> {
+        let mut env = BTreeMap::new();
+        if let Some(app_id) = self.steam_app_id {
+            for key in ["SteamAppId", "SteamGameId", "STEAM_COMPAT_APP_ID"] {
+                env.insert(key.to_string(), app_id.to_string());
+            }
+        }
+        if let Some(umu_id) = &self.umu_id {
+            env.insert("GAMEID".to_string(), umu_id.clone());
+            env.insert("UMU_ID".to_string(), umu_id.clone());
+        }
+        if let Some(store) = &self.store {
+            env.insert("STORE".to_string(), store.clone());
+        }
+        env
+    }
+
+    /// Save names of all games that have a config
+    pub fn list(paths: &Paths) -> std::io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        if !paths.games_dir().is_dir() {
+            return Ok(names);
+        }
+        for entry in std::fs::read_dir(paths.games_dir())? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "toml") {
+                names.push(path.file_stem().unwrap().to_string_lossy().to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Save name of the game that was added with this exe, if any
+    pub fn find_by_exe(paths: &Paths, exe: &Path) -> Option<String> {
+        let exe = std::fs::canonicalize(exe).ok()?;
+        Self::list(paths).ok()?.into_iter().find(|name| {
+            Self::load(paths, name).is_ok_and(|c| c.exe.as_deref() == Some(exe.as_path()))
+        })
+    }
+
+    pub fn save(&self, paths: &Paths, save_name: &str) -> std::io::Result<()> {
+        let content = toml::to_string(self).unwrap();
+        std::fs::create_dir_all(paths.games_dir())?;
+        std::fs::write(paths.game_config(save_name), content)
+    }
+}
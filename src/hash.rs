@@ -0,0 +1,29 @@
+use std::{fs::File, io::Read, path::Path};
+
+use sha2::{Digest, Sha256};
+
+/// The sha256 of a file as a hex string
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    hash_reader(File::open(path)?)
+}
+
+/// The sha256 of whatever `r` reads as a hex string
+pub fn hash_reader(mut r: impl Read) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut r, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// The sha256 of some data as a hex string
+pub fn hash_bytes(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// Whether `s` is a sha256 the way they are written, 64 lowercase hex digits
+pub fn is_hash(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::BTreeMap,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::Serialize;
+
+use crate::{proton::ProtonVersion, steam::SteamData};
+
+/// Everything needed to launch a game, fully resolved but not started yet
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchPlan {
+    pub exe: PathBuf,
+    pub proton: ProtonVersion,
+    pub proton_path: PathBuf,
+    pub save_name: String,
+    pub compat_dir: PathBuf,
+    pub run_dir: PathBuf,
+    /// Environment variables set on top of the current environment
+    pub env: BTreeMap<String, String>,
+    /// Commands the proton invocation is wrapped in, like `gamemoderun`
+    pub wrappers: Vec<String>,
+    /// The full command line, including wrappers
+    pub argv: Vec<String>,
+}
+
+impl LaunchPlan {
+    /// Plan to `proton run` an exe in `compat_dir`, args, game ids and wrappers can be added to it after
+    pub fn new(
+        proton: ProtonVersion,
+        proton_path: PathBuf,
+        steam_data: &SteamData,
+        save_name: String,
+        compat_dir: PathBuf,
+        run_dir: PathBuf,
+        exe: PathBuf,
+    ) -> Self {
+        Self {
+            env: proton_env(steam_data, &compat_dir),
+            argv: proton_argv(&proton_path, &exe),
+            exe,
+            proton,
+            proton_path,
+            save_name,
+            compat_dir,
+            run_dir,
+            wrappers: Vec::new(),
+        }
+    }
+
+    /// Run something else in the same prefix with the same proton and environment,
+    /// without the args and wrappers of this plan
+    pub fn run_other(&self, exe: impl Into<PathBuf>) -> Self {
+        let exe = exe.into();
+        Self {
+            argv: proton_argv(&self.proton_path, &exe),
+            exe,
+            wrappers: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Wrap the proton invocation in these commands, the first one is the outermost
+    pub fn wrap(&mut self, wrappers: &[String]) {
+        self.argv.splice(0..0, wrappers.iter().cloned());
+        self.wrappers.splice(0..0, wrappers.iter().cloned());
+    }
+
+    /// Create the command to execute this plan
+    pub fn command(&self) -> Command {
+        let (program, args) = self.argv.split_first().unwrap();
+        let mut command = Command::new(program);
+        command.args(args);
+        command.envs(&self.env);
+        command.current_dir(&self.run_dir);
+        // Put the game in its own process group, so `stop` can kill everything it started
+        command.process_group(0);
+        command
+    }
+
+    /// The plan as a command that can be pasted into a shell
+    pub fn to_shell(&self) -> String {
+        let mut parts = vec![
+            "cd".to_string(),
+            shell_quote(&self.run_dir.to_string_lossy()),
+            "&&".to_string(),
+        ];
+        parts.extend(
+            self.env
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, shell_quote(v))),
+        );
+        parts.extend(self.argv.iter().map(|a| shell_quote(a)));
+        parts.join(" ")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// The environment proton needs to run something in `compat_dir`
+pub fn proton_env(steam_data: &SteamData, compat_dir: &Path) -> BTreeMap<String, String> {
+    let mut env = BTreeMap::new();
+    env.insert(
+        "STEAM_COMPAT_CLIENT_INSTALL_PATH".to_string(),
+        steam_data.path.to_string_lossy().to_string(),
+    );
+    env.insert(
+        "STEAM_COMPAT_DATA_PATH".to_string(),
+        compat_dir.to_string_lossy().to_string(),
+    );
+    env
+}
+
+fn proton_argv(proton_path: &Path, exe: &Path) -> Vec<String> {
+    vec![
+        proton_path.join("proton").to_string_lossy().to_string(),
+        "run".to_string(),
+        exe.to_string_lossy().to_string(),
+    ]
+}
+
+fn shell_quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./,:=+@%".contains(c));
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
@@ -1,5 +1,19 @@
+pub mod backups;
+pub mod baseline;
 pub mod command;
+pub mod game;
+pub mod hash;
+pub mod launch;
+pub mod lnk;
+pub mod lock;
+pub mod ludusavi;
 pub mod paths;
+pub mod pe;
+pub mod prefix_archive;
 pub mod proton;
+pub mod registry;
+pub mod repository;
+pub mod session;
+pub mod shortcut;
 pub mod steam;
-pub mod shortcut;
\ No newline at end of file
+pub mod wine;
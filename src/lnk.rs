@@ -0,0 +1,383 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::wine::{find_compat_dir, find_prefix, split_command_line, unix_path};
+
+#[derive(Debug, Error)]
+pub enum LnkError {
+    #[error("Not a shell link file")]
+    NotALink,
+    #[error("Shell link has no target path")]
+    NoTarget,
+    #[error("Shell link file is truncated")]
+    Truncated,
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+}
+
+type LnkResult<T> = Result<T, LnkError>;
+
+const HEADER_SIZE: u32 = 0x4c;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+
+/// The parts of a Windows Shell Link (`.lnk`) file needed to launch its target.
+/// All paths are Windows paths, see [`crate::wine::unix_path`] to find them in a prefix
+#[derive(Debug, Clone, Default)]
+pub struct ShellLink {
+    pub name: Option<String>,
+    /// Full path of the target, like `C:\Games\Game\game.exe`
+    pub local_base_path: Option<String>,
+    /// Path of the target relative to the link file
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>,
+    pub icon_location: Option<String>,
+}
+
+impl ShellLink {
+    pub fn from_file(path: &Path) -> LnkResult<Self> {
+        let data = std::fs::read(path)?;
+        Self::parse(&data)
+    }
+
+    /// Parse a shell link as described in [MS-SHLLINK]
+    ///
+    /// [MS-SHLLINK]: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-shllink
+    pub fn parse(data: &[u8]) -> LnkResult<Self> {
+        let mut reader = Reader { data, pos: 0 };
+        if reader.u32()? != HEADER_SIZE || reader.bytes(16)? != LINK_CLSID {
+            return Err(LnkError::NotALink);
+        }
+        let flags = reader.u32()?;
+        reader.pos = HEADER_SIZE as usize;
+
+        if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            let size = reader.u16()?;
+            reader.bytes(size as usize)?;
+        }
+
+        let mut link = Self::default();
+        if flags & HAS_LINK_INFO != 0 {
+            let start = reader.pos;
+            let size = reader.u32()? as usize;
+            reader.bytes(size.checked_sub(4).ok_or(LnkError::Truncated)?)?;
+            link.local_base_path = Self::parse_link_info(&reader.data[start..reader.pos])?;
+        }
+
+        let unicode = flags & IS_UNICODE != 0;
+        let mut string = |flag: u32| -> LnkResult<Option<String>> {
+            if flags & flag != 0 {
+                reader.string_data(unicode).map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        link.name = string(HAS_NAME)?;
+        link.relative_path = string(HAS_RELATIVE_PATH)?;
+        link.working_dir = string(HAS_WORKING_DIR)?;
+        link.arguments = string(HAS_ARGUMENTS)?;
+        link.icon_location = string(HAS_ICON_LOCATION)?;
+
+        Ok(link)
+    }
+
+    /// Read the local base path and common path suffix from the LinkInfo structure,
+    /// offsets in it are relative to the start of the structure and can't point past its end
+    fn parse_link_info(structure: &[u8]) -> LnkResult<Option<String>> {
+        // Skip the size, the structure is already cut to it
+        let mut info = Reader {
+            data: structure,
+            pos: 4,
+        };
+        let header_size = info.u32()?;
+        let flags = info.u32()?;
+        let _volume_id_offset = info.u32()?;
+        let local_base_path_offset = info.u32()? as usize;
+        let _network_offset = info.u32()?;
+        let suffix_offset = info.u32()? as usize;
+        let (unicode_base_offset, unicode_suffix_offset) = if header_size >= 0x24 {
+            (Some(info.u32()? as usize), Some(info.u32()? as usize))
+        } else {
+            (None, None)
+        };
+
+        if flags & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+            return Ok(None);
+        }
+
+        let (base, suffix) = match (unicode_base_offset, unicode_suffix_offset) {
+            (Some(base), Some(suffix)) if base != 0 => (
+                read_utf16_z(structure, base)?,
+                read_utf16_z(structure, suffix)?,
+            ),
+            _ => (
+                read_ansi_z(structure, local_base_path_offset)?,
+                read_ansi_z(structure, suffix_offset)?,
+            ),
+        };
+        Ok(Some(base + &suffix))
+    }
+
+    /// Read a link file and resolve it against the prefix it is in.
+    /// Links created by installers live in the prefix they point into,
+    /// if the link is somewhere else the prefix from `fallback_prefix` is used.
+    pub fn resolve_file(
+        link_path: &Path,
+        fallback_prefix: impl FnOnce() -> PathBuf,
+    ) -> LnkResult<ResolvedLink> {
+        let link = Self::from_file(link_path)?;
+        match find_prefix(link_path) {
+            Some(prefix) => {
+                let mut resolved = link.resolve(link_path, &prefix)?;
+                resolved.compat_dir = find_compat_dir(&prefix);
+                Ok(resolved)
+            }
+            None => link.resolve(link_path, &fallback_prefix()),
+        }
+    }
+
+    /// Translate the link to something that can be launched from `prefix`
+    pub fn resolve(&self, link_path: &Path, prefix: &Path) -> LnkResult<ResolvedLink> {
+        let exe = if let Some(target) = &self.local_base_path {
+            unix_path(prefix, target)
+        } else if let Some(relative) = &self.relative_path {
+            let relative = relative.replace('\\', "/");
+            link_path.parent().unwrap().join(relative)
+        } else {
+            return Err(LnkError::NoTarget);
+        };
+        Ok(ResolvedLink {
+            exe,
+            working_dir: self
+                .working_dir
+                .as_deref()
+                .filter(|w| !w.is_empty())
+                .map(|w| unix_path(prefix, w)),
+            args: self
+                .arguments
+                .as_deref()
+                .map(split_command_line)
+                .unwrap_or_default(),
+            compat_dir: None,
+        })
+    }
+}
+
+/// A shell link with its paths translated to paths on this system
+#[derive(Debug, Clone)]
+pub struct ResolvedLink {
+    pub exe: PathBuf,
+    pub working_dir: Option<PathBuf>,
+    pub args: Vec<String>,
+    /// The compat dir the link was found in, if any
+    pub compat_dir: Option<PathBuf>,
+}
+
+/// Whether a path points to a shell link, based on the extension
+pub fn is_link(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("lnk"))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> LnkResult<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(LnkError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> LnkResult<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> LnkResult<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// A StringData entry, a character count followed by that many characters
+    fn string_data(&mut self, unicode: bool) -> LnkResult<String> {
+        let count = self.u16()? as usize;
+        if unicode {
+            let units: Vec<u16> = self
+                .bytes(count * 2)?
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            Ok(String::from_utf16_lossy(&units))
+        } else {
+            Ok(decode_ansi(self.bytes(count)?))
+        }
+    }
+}
+
+fn read_ansi_z(data: &[u8], offset: usize) -> LnkResult<String> {
+    let bytes = data.get(offset..).ok_or(LnkError::Truncated)?;
+    let end = bytes
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or(LnkError::Truncated)?;
+    Ok(decode_ansi(&bytes[..end]))
+}
+
+fn read_utf16_z(data: &[u8], offset: usize) -> LnkResult<String> {
+    let bytes = data.get(offset..).ok_or(LnkError::Truncated)?;
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|u| *u != 0)
+        .collect();
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// ANSI strings use the system code page, Latin-1 is the closest we can get without knowing it
+fn decode_ansi(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(flags: u32) -> Vec<u8> {
+        let mut data = HEADER_SIZE.to_le_bytes().to_vec();
+        data.extend(LINK_CLSID);
+        data.extend(flags.to_le_bytes());
+        data.resize(HEADER_SIZE as usize, 0);
+        data
+    }
+
+    fn unicode_string(data: &mut Vec<u8>, s: &str) {
+        let units: Vec<u16> = s.encode_utf16().collect();
+        data.extend((units.len() as u16).to_le_bytes());
+        data.extend(units.iter().flat_map(|u| u.to_le_bytes()));
+    }
+
+    /// A link to `C:\Games\Game\game.exe` the way Windows writes them, with an ID list to skip
+    fn local_link() -> Vec<u8> {
+        let mut data = header(
+            HAS_LINK_TARGET_ID_LIST | HAS_LINK_INFO | HAS_WORKING_DIR | HAS_ARGUMENTS | IS_UNICODE,
+        );
+        data.extend(4u16.to_le_bytes());
+        data.extend([0xff; 4]);
+
+        let volume_id = [0x10, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0];
+        let base = b"C:\\Games\\Game\\\0";
+        let suffix = b"game.exe\0";
+        let base_offset = 0x1c + volume_id.len() as u32;
+        let suffix_offset = base_offset + base.len() as u32;
+        let size = suffix_offset + suffix.len() as u32;
+        for field in [
+            size,
+            0x1c,
+            VOLUME_ID_AND_LOCAL_BASE_PATH,
+            0x1c,
+            base_offset,
+            0,
+            suffix_offset,
+        ] {
+            data.extend(field.to_le_bytes());
+        }
+        data.extend(volume_id);
+        data.extend(base);
+        data.extend(suffix);
+
+        unicode_string(&mut data, "C:\\Games\\Game");
+        unicode_string(&mut data, "-novid \"-w 1920\"");
+        data
+    }
+
+    #[test]
+    fn parses_a_link_with_a_local_base_path() {
+        let link = ShellLink::parse(&local_link()).unwrap();
+        assert_eq!(
+            link.local_base_path.as_deref(),
+            Some("C:\\Games\\Game\\game.exe")
+        );
+        assert_eq!(link.working_dir.as_deref(), Some("C:\\Games\\Game"));
+        assert_eq!(link.relative_path, None);
+
+        let resolved = link
+            .resolve(
+                Path::new("/pfx/drive_c/users/Public/Desktop/Game.lnk"),
+                Path::new("/pfx"),
+            )
+            .unwrap();
+        assert_eq!(resolved.exe, Path::new("/pfx/drive_c/Games/Game/game.exe"));
+        assert_eq!(
+            resolved.working_dir.as_deref(),
+            Some(Path::new("/pfx/drive_c/Games/Game"))
+        );
+        assert_eq!(resolved.args, ["-novid", "-w 1920"]);
+    }
+
+    #[test]
+    fn parses_a_link_with_only_a_relative_path() {
+        let mut data = header(HAS_NAME | HAS_RELATIVE_PATH);
+        for s in ["The Game", "..\\bin\\game.exe"] {
+            data.extend((s.len() as u16).to_le_bytes());
+            data.extend(s.as_bytes());
+        }
+        let link = ShellLink::parse(&data).unwrap();
+        assert_eq!(link.name.as_deref(), Some("The Game"));
+        assert_eq!(link.local_base_path, None);
+
+        let resolved = link
+            .resolve(Path::new("/games/Game/links/game.lnk"), Path::new("/pfx"))
+            .unwrap();
+        assert_eq!(resolved.exe, Path::new("/games/Game/links/../bin/game.exe"));
+        assert_eq!(resolved.working_dir, None);
+        assert!(resolved.args.is_empty());
+
+        let empty = ShellLink::parse(&header(0)).unwrap();
+        assert!(matches!(
+            empty.resolve(Path::new("/game.lnk"), Path::new("/pfx")),
+            Err(LnkError::NoTarget)
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_and_other_files() {
+        let data = local_link();
+        for len in 0..data.len() {
+            assert!(ShellLink::parse(&data[..len]).is_err(), "{} bytes", len);
+        }
+        let mut not_a_link = data.clone();
+        not_a_link[4] = 0;
+        assert!(matches!(
+            ShellLink::parse(&not_a_link),
+            Err(LnkError::NotALink)
+        ));
+
+        // An offset in the LinkInfo pointing past its end, into the strings after it
+        let mut bad_offset = data;
+        let info = HEADER_SIZE as usize + 6;
+        let size = u32::from_le_bytes(bad_offset[info..info + 4].try_into().unwrap());
+        bad_offset[info + 24..info + 28].copy_from_slice(&(size + 2).to_le_bytes());
+        assert!(matches!(
+            ShellLink::parse(&bad_offset),
+            Err(LnkError::Truncated)
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error("{} is in use by {holder}, pass --wait-lock to wait for it", .path.display())]
+    Locked { path: PathBuf, holder: String },
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+}
+
+/// Advisory lock on a compat dir, released when dropped.
+///
+/// The lock is an `flock` on a file next to the compat dir (not inside it, so it never ends up in backups or copies).
+/// The file is never removed, so there is nothing to race on, and the kernel releases the lock when the holder exits.
+/// The pid and command line of the holder are written into it, but only to tell the user who has it.
+#[derive(Debug)]
+pub struct PrefixLock {
+    file: File,
+    compat_dir: PathBuf,
+}
+
+impl PrefixLock {
+    pub fn lock_path(compat_dir: &Path) -> PathBuf {
+        with_suffix(compat_dir, ".lock")
+    }
+
+    /// Try to take the lock, failing if someone else holds it
+    pub fn try_acquire(compat_dir: &Path) -> Result<Self, LockError> {
+        let path = Self::lock_path(compat_dir);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(LockError::Locked {
+                    path: compat_dir.to_path_buf(),
+                    holder: Self::holder(&path),
+                })
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        let command = std::env::args().collect::<Vec<_>>().join(" ");
+        file.set_len(0)?;
+        writeln!(file, "{}\n{}", std::process::id(), command)?;
+        Ok(Self {
+            file,
+            compat_dir: compat_dir.to_path_buf(),
+        })
+    }
+
+    /// The compat dir this locks
+    pub fn compat_dir(&self) -> &Path {
+        &self.compat_dir
+    }
+
+    /// Take the lock, waiting for the current holder to release it
+    pub fn acquire_wait(compat_dir: &Path) -> Result<Self, LockError> {
+        let mut told = false;
+        loop {
+            match Self::try_acquire(compat_dir) {
+                Err(LockError::Locked { holder, .. }) => {
+                    if !told {
+                        println!("Waiting for {holder} to release the prefix");
+                        told = true;
+                    }
+                    std::thread::sleep(Duration::from_millis(500));
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Who holds the lock, as far as they wrote it down yet
+    fn holder(path: &Path) -> String {
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        let mut lines = content.lines();
+        match lines.next().and_then(|pid| pid.parse::<u32>().ok()) {
+            Some(pid) => format!("pid {} ({})", pid, lines.next().unwrap_or_default()),
+            None => "another process".to_string(),
+        }
+    }
+}
+
+impl Drop for PrefixLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock, the file itself stays for the next one
+        let _ = self.file.set_len(0);
+    }
+}
+
+/// `path` with `suffix` added to its file name, unlike `with_extension` this keeps dots in save names like `Foo 1.2`
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LudusaviError {
+    #[error("Could not read the ludusavi manifest: {}", .0)]
+    IOError(#[from] std::io::Error),
+
+    #[error("Could not parse the ludusavi manifest: {}", .0)]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+/// The ludusavi manifest, which knows where lots of games keep their saves.
+/// See <https://github.com/mtkennerly/ludusavi-manifest> for the format.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest(BTreeMap<String, Game>);
+
+/// Where a single game keeps its saves, with placeholders like `<winAppData>` in the paths
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Game {
+    /// Another game in the manifest this one is the same as
+    pub alias: Option<String>,
+    pub files: BTreeMap<String, Entry>,
+    pub registry: BTreeMap<String, Entry>,
+    /// Names of the folder the game is usually installed in
+    pub install_dir: BTreeMap<String, serde_yaml::Value>,
+    pub steam: Option<SteamInfo>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Entry {
+    pub tags: Vec<String>,
+    pub when: Vec<Constraint>,
+}
+
+/// An entry only applies if one of its constraints matches, or if it has none
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Constraint {
+    pub os: Option<String>,
+    pub store: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SteamInfo {
+    pub id: Option<u64>,
+}
+
+/// The saves of a game in a prefix
+#[derive(Debug, Clone, Default)]
+pub struct SaveLocations {
+    /// Files that exist, with absolute paths
+    pub files: Vec<PathBuf>,
+    /// Registry keys like `HKEY_CURRENT_USER/Software/Vendor/Game`
+    pub registry: Vec<String>,
+}
+
+impl Manifest {
+    /// Where ludusavi itself keeps the manifest it downloads
+    pub fn default_path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("ludusavi").join("manifest.yaml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LudusaviError> {
+        let f = std::fs::File::open(path)?;
+        Ok(serde_yaml::from_reader(f)?)
+    }
+
+    /// Find a game by its name in the manifest, or by its Steam app id
+    pub fn find(&self, name: Option<&str>, steam_id: Option<u64>) -> Option<(&str, &Game)> {
+        let found = name
+            .and_then(|name| self.0.get_key_value(name))
+            .or_else(|| {
+                let id = steam_id?;
+                self.0
+                    .iter()
+                    .find(|(_, game)| game.steam.as_ref().and_then(|s| s.id) == Some(id))
+            })?;
+        match &found.1.alias {
+            Some(alias) => self.0.get_key_value(alias.as_str()),
+            None => Some(found),
+        }
+        .map(|(name, game)| (name.as_str(), game))
+    }
+}
+
+impl Game {
+    /// The directory the game is installed in, which is `<base>` in the manifest.
+    /// This is the closest folder above the exe named like one of the known install dirs,
+    /// or the folder of the exe if there is none.
+    pub fn base_dir(&self, exe: &Path) -> PathBuf {
+        exe.ancestors()
+            .skip(1)
+            .find(|dir| {
+                dir.file_name().is_some_and(|name| {
+                    self.install_dir
+                        .keys()
+                        .any(|d| name.to_string_lossy().eq_ignore_ascii_case(d))
+                })
+            })
+            .unwrap_or_else(|| exe.parent().unwrap())
+            .to_path_buf()
+    }
+
+    /// Map the paths of the manifest onto the prefix and the game dir and find the files they match
+    pub fn resolve(&self, prefix: &Path, base_dir: Option<&Path>) -> SaveLocations {
+        let options = MatchOptions {
+            case_sensitive: false,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let mut files = Vec::new();
+        for (path, entry) in &self.files {
+            if !entry.applies() {
+                continue;
+            }
+            let Some(pattern) = expand(path, prefix, base_dir) else {
+                continue;
+            };
+            let Ok(matches) = glob::glob_with(&pattern, options) else {
+                continue;
+            };
+            for path in matches.flatten() {
+                if path.is_dir() {
+                    files.extend(
+                        walkdir::WalkDir::new(&path)
+                            .into_iter()
+                            .flatten()
+                            .filter(|e| e.file_type().is_file())
+                            .map(|e| e.into_path()),
+                    );
+                } else if path.is_file() {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+
+        let registry = self
+            .registry
+            .iter()
+            .filter(|(_, entry)| entry.applies())
+            .map(|(key, _)| key.clone())
+            .collect();
+        SaveLocations { files, registry }
+    }
+}
+
+impl Entry {
+    /// Whether the entry is about the Windows version of the game, which is what runs in proton
+    fn applies(&self) -> bool {
+        self.when.is_empty()
+            || self
+                .when
+                .iter()
+                .any(|c| c.os.as_deref().is_none_or(|os| os == "windows"))
+    }
+}
+
+/// Replace the placeholders in a path from the manifest with where they are in the prefix,
+/// giving a glob pattern. Paths that only make sense outside of wine give `None`.
+fn expand(path: &str, prefix: &Path, base_dir: Option<&Path>) -> Option<String> {
+    let escape = |p: &Path| Pattern::escape(&p.to_string_lossy());
+    let drive_c = prefix.join("drive_c");
+    let home = drive_c.join("users").join("steamuser");
+    let mut expanded = path.to_string();
+    let replacements = [
+        ("<winAppData>", escape(&home.join("AppData/Roaming"))),
+        (
+            "<winLocalAppDataLow>",
+            escape(&home.join("AppData/LocalLow")),
+        ),
+        ("<winLocalAppData>", escape(&home.join("AppData/Local"))),
+        ("<winDocuments>", escape(&home.join("Documents"))),
+        ("<winPublic>", escape(&drive_c.join("users/Public"))),
+        ("<winProgramData>", escape(&drive_c.join("ProgramData"))),
+        ("<winDir>", escape(&drive_c.join("windows"))),
+        ("<home>", escape(&home)),
+        ("<osUserName>", "steamuser".to_string()),
+        ("<storeUserId>", "*".to_string()),
+    ];
+    for (placeholder, value) in replacements {
+        expanded = expanded.replace(placeholder, &value);
+    }
+    if let Some(base_dir) = base_dir {
+        expanded = expanded.replace("<base>", &escape(base_dir));
+        if let Some(root) = base_dir.parent() {
+            expanded = expanded.replace("<root>", &escape(root));
+        }
+        if let Some(game) = base_dir.file_name() {
+            expanded = expanded.replace("<game>", &Pattern::escape(&game.to_string_lossy()));
+        }
+    }
+    // Anything left is a placeholder we can't fill, like `<xdgConfig>` or `<base>` without a game dir
+    (!expanded.contains('<')).then_some(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+Half-Life 2:
+  files:
+    <base>/hl2/save:
+      tags: [save]
+    <winDocuments>/My Games/<game>/*.cfg:
+      when:
+        - os: windows
+    <home>/.local/share/hl2:
+      when:
+        - os: linux
+    <xdgConfig>/hl2:
+      tags: [config]
+  registry:
+    HKEY_CURRENT_USER/Software/Valve/Half-Life 2:
+      tags: [config]
+    HKEY_CURRENT_USER/Software/Valve/Linux:
+      when:
+        - os: linux
+  installDir:
+    Half-Life 2: {}
+  steam:
+    id: 220
+Half-Life 2 (Steam):
+  alias: Half-Life 2
+"#;
+
+    fn manifest() -> Manifest {
+        serde_yaml::from_str(MANIFEST).unwrap()
+    }
+
+    #[test]
+    fn finds_games_by_name_alias_and_steam_id() {
+        let manifest = manifest();
+        assert_eq!(
+            manifest.find(Some("Half-Life 2"), None).unwrap().0,
+            "Half-Life 2"
+        );
+        assert_eq!(
+            manifest.find(Some("Half-Life 2 (Steam)"), None).unwrap().0,
+            "Half-Life 2"
+        );
+        assert_eq!(
+            manifest.find(Some("Portal"), Some(220)).unwrap().0,
+            "Half-Life 2"
+        );
+        assert!(manifest.find(Some("Portal"), Some(400)).is_none());
+        assert!(manifest.find(None, None).is_none());
+    }
+
+    #[test]
+    fn finds_the_install_dir_above_the_exe() {
+        let manifest = manifest();
+        let (_, game) = manifest.find(Some("Half-Life 2"), None).unwrap();
+        assert_eq!(
+            game.base_dir(Path::new("/games/half-life 2/bin/hl2.exe")),
+            Path::new("/games/half-life 2")
+        );
+        assert_eq!(
+            game.base_dir(Path::new("/games/HL2/bin/hl2.exe")),
+            Path::new("/games/HL2/bin")
+        );
+    }
+
+    #[test]
+    fn only_windows_entries_apply() {
+        let manifest = manifest();
+        let (_, game) = manifest.find(Some("Half-Life 2"), None).unwrap();
+        let applies: Vec<&str> = game
+            .files
+            .iter()
+            .filter(|(_, entry)| entry.applies())
+            .map(|(path, _)| path.as_str())
+            .collect();
+        assert_eq!(
+            applies,
+            [
+                "<base>/hl2/save",
+                "<winDocuments>/My Games/<game>/*.cfg",
+                "<xdgConfig>/hl2"
+            ]
+        );
+        let saves = game.resolve(Path::new("/nonexistent/pfx"), None);
+        assert!(saves.files.is_empty());
+        assert_eq!(
+            saves.registry,
+            ["HKEY_CURRENT_USER/Software/Valve/Half-Life 2"]
+        );
+    }
+
+    #[test]
+    fn expands_placeholders_into_the_prefix() {
+        let prefix = Path::new("/data/compat/hl2/pfx");
+        let base_dir = Path::new("/games/Half-Life 2 [GOTY]");
+        assert_eq!(
+            expand("<winAppData>/Valve/<osUserName>", prefix, None).unwrap(),
+            "/data/compat/hl2/pfx/drive_c/users/steamuser/AppData/Roaming/Valve/steamuser"
+        );
+        assert_eq!(
+            expand("<winLocalAppDataLow>/Valve/<storeUserId>", prefix, None).unwrap(),
+            "/data/compat/hl2/pfx/drive_c/users/steamuser/AppData/LocalLow/Valve/*"
+        );
+        // Glob characters in the paths can't match anything but themselves
+        assert_eq!(
+            expand("<base>/save/*.sav", prefix, Some(base_dir)).unwrap(),
+            "/games/Half-Life 2 [[]GOTY[]]/save/*.sav"
+        );
+        assert_eq!(
+            expand("<root>/<game>", prefix, Some(base_dir)).unwrap(),
+            "/games/Half-Life 2 [[]GOTY[]]"
+        );
+        assert_eq!(expand("<base>/save", prefix, None), None);
+        assert_eq!(expand("<xdgConfig>/hl2", prefix, Some(base_dir)), None);
+    }
+}
@@ -22,11 +22,6 @@ pub struct ProtonLaunch {
     /// (It has to contain a steamapps folder)
     #[arg(long, short)]
     steam_path: Option<PathBuf>,
-
-    /// Use local compat folder instead of the global one
-    /// This is useful if you want to keep the game files locally
-    #[arg(long, short, default_value_t)]
-    local: bool,
 }
 
 fn main() {
@@ -41,6 +36,6 @@ fn main() {
     let res = command.run(paths, &steam_data);
     if let Err(e) = res {
         eprintln!("Error: {}", e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
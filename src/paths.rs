@@ -1,7 +1,18 @@
-use std::{fmt::Display, ops::Deref, path::PathBuf, str::FromStr};
+use std::{
+    fmt::Display,
+    ops::Deref,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use xdg::BaseDirectories;
 
+use crate::{
+    game::GameConfig,
+    lock::{LockError, PrefixLock},
+    repository::Repository,
+};
+
 #[derive(Debug, Clone)]
 pub struct DataDir(PathBuf);
 
@@ -15,9 +26,9 @@ impl FromStr for DataDir {
 
 impl Default for DataDir {
     fn default() -> Self {
+        // Only the folders that get written to are created, not just for parsing the args
         let basedirs = BaseDirectories::new().unwrap();
-        let data_dir = basedirs.create_data_directory("proton-launch").unwrap();
-        Self(data_dir)
+        Self(basedirs.get_data_home().join("proton-launch"))
     }
 }
 
@@ -37,17 +48,49 @@ impl Deref for DataDir {
 
 impl DataDir {
     pub fn compat_dir(&self, app_id: &str) -> PathBuf {
-        let compat_dir = self.0.join("compat").join(app_id);
+        let compat_dir = self.compat_path(app_id);
         std::fs::create_dir_all(&compat_dir).unwrap();
         compat_dir
     }
 
+    /// Like [`DataDir::compat_dir`], without creating it
+    pub fn compat_path(&self, app_id: &str) -> PathBuf {
+        self.0.join("compat").join(app_id)
+    }
+
     pub fn run_dir(&self, app_id: &str) -> PathBuf {
-        let run_dir = self.0.join("run").join(app_id);
+        let run_dir = self.run_path(app_id);
         std::fs::create_dir_all(&run_dir).unwrap();
         run_dir
     }
 
+    /// Like [`DataDir::run_dir`], without creating it
+    pub fn run_path(&self, app_id: &str) -> PathBuf {
+        self.0.join("run").join(app_id)
+    }
+
+    pub fn backups_dir(&self, app_id: &str) -> PathBuf {
+        let backups_dir = self.0.join("backups").join(app_id);
+        std::fs::create_dir_all(&backups_dir).unwrap();
+        backups_dir
+    }
+
+    /// The directory holding the backups dirs of all games
+    pub fn all_backups_dir(&self) -> PathBuf {
+        self.0.join("backups")
+    }
+
+    pub fn repository_dir(&self) -> PathBuf {
+        let repository_dir = self.repository_path();
+        std::fs::create_dir_all(&repository_dir).unwrap();
+        repository_dir
+    }
+
+    /// Like [`DataDir::repository_dir`], without creating it
+    pub fn repository_path(&self) -> PathBuf {
+        self.0.join("repository")
+    }
+
     pub fn icon_path(&self, app_id: &str) -> PathBuf {
         let icons_dir = self.0.join("icons");
         std::fs::create_dir_all(&icons_dir).unwrap();
@@ -69,8 +112,7 @@ impl FromStr for ConfigDir {
 impl Default for ConfigDir {
     fn default() -> Self {
         let basedirs = BaseDirectories::new().unwrap();
-        let config_dir = basedirs.create_config_directory("proton-launch").unwrap();
-        Self(config_dir)
+        Self(basedirs.get_config_home().join("proton-launch"))
     }
 }
 
@@ -99,21 +141,140 @@ pub struct Paths {
     /// This is both Global and Game specific config
     #[cfg_attr(feature = "commandline", arg(short, long, default_value_t))]
     config_dir: ConfigDir,
+    /// Wait for other commands using the same compat folder to finish instead of failing
+    #[cfg_attr(feature = "commandline", arg(long, default_value_t))]
+    wait_lock: bool,
+    /// Use local compat folder instead of the global one
+    /// This is useful if you want to keep the game files locally
+    #[cfg_attr(feature = "commandline", arg(long, short, default_value_t))]
+    local: bool,
 }
 
 impl Paths {
-    pub fn compat_dir(&self, app_id: &str) -> PathBuf {
+    /// Paths with the data and config dirs inside `dir`, so tests don't touch the real ones
+    #[cfg(test)]
+    pub fn in_dir(dir: &Path, local: bool) -> Self {
+        Self {
+            data_dir: DataDir(dir.join("data")),
+            config_dir: ConfigDir(dir.join("config")),
+            wait_lock: false,
+            local,
+        }
+    }
+
+    /// The compat dir of a game.
+    /// This is the `compat` folder next to the exe if `--local` is passed or the game is configured to be local,
+    /// otherwise it's the global one in the data dir.
+    /// If no exe is given, the one the game was added with is used.
+    /// Games can also be added with a specific compat dir, which takes precedence over all of this.
+    /// Fails if the config of the game can't be read, rather than quietly using another prefix.
+    pub fn compat_dir(&self, app_id: &str, exe: Option<&Path>) -> std::io::Result<PathBuf> {
+        let compat_dir = self.compat_path(app_id, exe)?;
+        std::fs::create_dir_all(&compat_dir)?;
+        Ok(compat_dir)
+    }
+
+    /// Like [`Paths::compat_dir`], without creating it, for things like dry runs that must not touch the disk
+    pub fn compat_path(&self, app_id: &str, exe: Option<&Path>) -> std::io::Result<PathBuf> {
+        let config = GameConfig::load(self, app_id)?;
+        if let Some(compat_dir) = config.compat_dir {
+            return Ok(compat_dir);
+        }
+        let exe = exe.or(config.exe.as_deref());
+        Ok(match exe {
+            Some(exe) if self.local || config.local => self.local_compat_dir(exe),
+            _ => self.data_dir.compat_path(app_id),
+        })
+    }
+
+    pub fn global_compat_dir(&self, app_id: &str) -> PathBuf {
         self.data_dir.compat_dir(app_id)
     }
 
+    /// The `compat` folder next to the exe, the same layout `move-compat` creates
+    pub fn local_compat_dir(&self, exe: &Path) -> PathBuf {
+        exe.parent().unwrap().join("compat")
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+
     pub fn run_dir(&self, app_id: &str) -> PathBuf {
         self.data_dir.run_dir(app_id)
     }
 
+    /// Like [`Paths::run_dir`], without creating it
+    pub fn run_path(&self, app_id: &str) -> PathBuf {
+        self.data_dir.run_path(app_id)
+    }
+
+    /// Whether anything is stored under this save name yet, like a prefix, backups or a config
+    pub fn is_used(&self, app_id: &str) -> bool {
+        GameConfig::exists(self, app_id)
+            || self.data_dir.compat_path(app_id).exists()
+            || self.data_dir.all_backups_dir().join(app_id).exists()
+    }
+
+    /// The directory the backups of a game are stored in
+    pub fn backups_dir(&self, app_id: &str) -> PathBuf {
+        self.data_dir.backups_dir(app_id)
+    }
+
+    pub fn repository_dir(&self) -> PathBuf {
+        self.data_dir.repository_dir()
+    }
+
+    /// The deduplicated backup repository, shared by all games
+    pub fn repository(&self) -> Repository {
+        Repository::new(self.repository_dir())
+    }
+
+    /// Like [`Paths::repository`], without creating its folder, for reading from it
+    pub fn existing_repository(&self) -> Repository {
+        Repository::new(self.data_dir.repository_path())
+    }
+
+    /// The snapshots of all games in the repository
+    pub fn snapshots(&self) -> std::io::Result<Vec<PathBuf>> {
+        let mut snapshots = Vec::new();
+        let dir = self.data_dir.all_backups_dir();
+        if !dir.is_dir() {
+            return Ok(snapshots);
+        }
+        for entry in walkdir::WalkDir::new(dir).min_depth(2).max_depth(2) {
+            let path = entry?.into_path();
+            if Repository::is_snapshot(&path) {
+                snapshots.push(path);
+            }
+        }
+        snapshots.sort();
+        Ok(snapshots)
+    }
+
     pub fn icon_path(&self, app_id: &str) -> PathBuf {
         self.data_dir.icon_path(app_id)
     }
 
+    /// The directory containing the config files of all games, created when the first one is saved
+    pub fn games_dir(&self) -> PathBuf {
+        self.config_dir.join("games")
+    }
+
+    /// Path to the config file of a single game
+    pub fn game_config(&self, app_id: &str) -> PathBuf {
+        self.games_dir().join(format!("{}.toml", app_id))
+    }
+
+    /// Lock a compat dir so no other proton-launch command uses it at the same time
+    pub fn lock_compat_dir(&self, compat_dir: &Path) -> Result<PrefixLock, LockError> {
+        if self.wait_lock {
+            PrefixLock::acquire_wait(compat_dir)
+        } else {
+            PrefixLock::try_acquire(compat_dir)
+        }
+    }
+
     pub fn application_entry(&self, app_id: &str) -> PathBuf {
         let mut path = dirs::data_dir().unwrap().join("applications");
         std::fs::create_dir_all(&path).unwrap();
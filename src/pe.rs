@@ -0,0 +1,175 @@
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use exe::{Arch, CCharString, ImageDirectoryEntry, ImportDirectory, VSVersionInfo, VecPE, PE};
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PeError {
+    #[error("Could not read exe: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Not a valid exe: {0}")]
+    Exe(#[from] exe::Error),
+}
+
+type PeResult<T> = Result<T, PeError>;
+
+/// The engine or framework a game is built with, as far as we can tell from the files around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Engine {
+    Unity,
+    Unreal,
+    Electron,
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// What we know about an exe from its PE headers and the files next to it
+#[derive(Debug, Clone, Serialize)]
+pub struct ExeInfo {
+    pub is_64_bit: bool,
+    /// Whether the exe is a .NET assembly
+    pub dotnet: bool,
+    pub engine: Option<Engine>,
+    /// Names of the DLLs the exe imports
+    pub imports: Vec<String>,
+    /// The strings in the VS_VERSIONINFO resource, like `ProductName` and `CompanyName`
+    pub version_info: BTreeMap<String, String>,
+}
+
+/// A setting that will probably help a game run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Suggestion {
+    /// Run the game in the directory of the exe
+    RunHere,
+    /// Pass an extra arg to the game
+    Arg(String),
+    /// Apply a winetricks verb to the prefix
+    Winetricks(String),
+}
+
+impl Display for Suggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Suggestion::RunHere => write!(f, "run it in the directory of the exe with `--here`"),
+            Suggestion::Arg(arg) => write!(f, "pass `{}` to the game", arg),
+            Suggestion::Winetricks(verb) => {
+                write!(
+                    f,
+                    "install {verb} with `proton-launch winetricks <game> {verb}`"
+                )
+            }
+        }
+    }
+}
+
+impl ExeInfo {
+    pub fn inspect(path: &Path) -> PeResult<Self> {
+        let pe = VecPE::from_disk_file(path)?;
+
+        let is_64_bit = pe.get_arch()? == Arch::X64;
+        let dotnet = pe.has_data_directory(ImageDirectoryEntry::COMDescriptor);
+
+        let mut imports = Vec::new();
+        if pe.has_data_directory(ImageDirectoryEntry::Import) {
+            let directory = ImportDirectory::parse(&pe)?;
+            for descriptor in directory.descriptors {
+                imports.push(descriptor.get_name(&pe)?.as_str()?.to_string());
+            }
+        }
+
+        // Not every exe has version info, that's fine
+        let version_info = VSVersionInfo::parse(&pe)
+            .ok()
+            .and_then(|v| v.string_file_info)
+            .and_then(|s| s.children.first().and_then(|t| t.string_map().ok()))
+            .map(|m| m.into_iter().collect())
+            .unwrap_or_default();
+
+        Ok(Self {
+            is_64_bit,
+            dotnet,
+            engine: detect_engine(path),
+            imports,
+            version_info,
+        })
+    }
+
+    pub fn product_name(&self) -> Option<&str> {
+        self.version_string("ProductName")
+    }
+
+    pub fn file_description(&self) -> Option<&str> {
+        self.version_string("FileDescription")
+    }
+
+    pub fn company_name(&self) -> Option<&str> {
+        self.version_string("CompanyName")
+    }
+
+    pub fn product_version(&self) -> Option<&str> {
+        self.version_string("ProductVersion")
+    }
+
+    /// The name of the game according to its version info
+    pub fn display_name(&self) -> Option<&str> {
+        self.product_name().or(self.file_description())
+    }
+
+    /// A save name based on the display name, with everything that doesn't belong in a file name removed
+    pub fn save_name(&self) -> Option<String> {
+        let name: String = self
+            .display_name()?
+            .chars()
+            .filter(|c| c.is_alphanumeric() || " -_.".contains(*c))
+            .collect();
+        let name = name.trim().trim_start_matches('.').to_string();
+        Some(name).filter(|n| !n.is_empty())
+    }
+
+    /// A version string, ignoring empty ones since plenty of exes have those
+    fn version_string(&self, key: &str) -> Option<&str> {
+        self.version_info
+            .get(key)
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Settings that are known to help games like this one
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        let mut suggestions = Vec::new();
+        match self.engine {
+            // Unity looks for its data folder relative to the working directory
+            Some(Engine::Unity) => suggestions.push(Suggestion::RunHere),
+            // The chromium sandbox does not work under wine
+            Some(Engine::Electron) => suggestions.push(Suggestion::Arg("--no-sandbox".to_string())),
+            _ => {}
+        }
+        if self.dotnet {
+            suggestions.push(Suggestion::Winetricks("dotnet48".to_string()));
+        }
+        suggestions
+    }
+}
+
+fn detect_engine(exe: &Path) -> Option<Engine> {
+    let dir = exe.parent()?;
+    let stem = exe.file_stem()?.to_string_lossy();
+    if dir.join("UnityPlayer.dll").is_file() || dir.join(format!("{}_Data", stem)).is_dir() {
+        Some(Engine::Unity)
+    } else if stem.ends_with("-Shipping")
+        || exe.ancestors().any(|a| a.join("Engine/Binaries").is_dir())
+    {
+        Some(Engine::Unreal)
+    } else if dir.join("resources/app.asar").is_file()
+        || dir.join("resources/electron.asar").is_file()
+    {
+        Some(Engine::Electron)
+    } else {
+        None
+    }
+}
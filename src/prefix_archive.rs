@@ -0,0 +1,242 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::backups::host_name;
+
+/// Name of the entry at the start of a prefix archive that describes it
+pub const PREFIX_MANIFEST: &str = "prefix.json";
+
+/// Describes an exported compat dir and the machine it came from,
+/// so absolute paths in it can be pointed at the right places when it is imported somewhere else
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefixManifest {
+    /// Name of the game, if it was added with one
+    pub name: Option<String>,
+    pub save_name: String,
+    /// When the prefix was exported, in seconds since the unix epoch
+    pub created: i64,
+    /// Machine the prefix was exported on
+    pub host: String,
+    /// Proton version the prefix was last used with
+    pub proton: Option<String>,
+    /// Where that proton was installed
+    pub proton_path: Option<PathBuf>,
+    /// Where the compat dir was
+    pub compat_dir: PathBuf,
+    /// Where Steam was installed
+    pub steam_path: PathBuf,
+    /// Home directory of the user that exported the prefix
+    pub home: Option<PathBuf>,
+    /// Files with holes in them, relative to the compat dir
+    #[serde(default)]
+    pub sparse: Vec<PathBuf>,
+}
+
+impl PrefixManifest {
+    pub fn new(save_name: &str, compat_dir: &Path, steam_path: &Path) -> Self {
+        Self {
+            name: None,
+            save_name: save_name.to_string(),
+            created: OffsetDateTime::now_utc().unix_timestamp(),
+            host: host_name(),
+            proton: None,
+            proton_path: None,
+            compat_dir: compat_dir.to_path_buf(),
+            steam_path: steam_path.to_path_buf(),
+            home: dirs::home_dir(),
+            sparse: Vec::new(),
+        }
+    }
+
+    /// Read the manifest from the start of a prefix archive, `None` if the archive has none
+    pub fn read(archive: &Path) -> std::io::Result<Option<Self>> {
+        let d = zstd::Decoder::new(File::open(archive)?)?;
+        let mut archive = tar::Archive::new(d);
+        let Some(entry) = archive.entries()?.next() else {
+            return Ok(None);
+        };
+        let mut entry = entry?;
+        if entry.path()?.as_ref() != Path::new(PREFIX_MANIFEST) {
+            return Ok(None);
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Whether a file takes up less space on disk than its size, because parts of it were never written
+pub fn is_sparse(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.is_file() && metadata.blocks() * 512 < metadata.len()
+}
+
+/// Write the contents of `r` to a new file, leaving holes where whole blocks are zero
+pub fn write_sparse<R: Read>(mut r: R, path: &Path, size: u64) -> std::io::Result<File> {
+    let mut f = File::create(path)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = r.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if buf[..n].iter().all(|b| *b == 0) {
+            f.seek(SeekFrom::Current(n as i64))?;
+        } else {
+            f.write_all(&buf[..n])?;
+        }
+    }
+    // A hole at the end is only there once the size is set
+    f.set_len(size)?;
+    Ok(f)
+}
+
+/// Replaces absolute paths of one machine with the ones they correspond to on another
+#[derive(Debug, Clone, Default)]
+pub struct PathRewriter {
+    paths: Vec<(PathBuf, PathBuf)>,
+}
+
+impl PathRewriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewrite `old` and everything below it to `new`
+    pub fn add(&mut self, old: &Path, new: &Path) {
+        if old != new && old.is_absolute() {
+            self.paths.push((old.to_path_buf(), new.to_path_buf()));
+            // The most specific path wins, the compat dir is usually below the home dir
+            self.paths
+                .sort_by_key(|(old, _)| std::cmp::Reverse(old.as_os_str().len()));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// The rewritten path, `None` if it isn't below any of the old paths
+    pub fn rewrite_path(&self, path: &Path) -> Option<PathBuf> {
+        self.paths.iter().find_map(|(old, new)| {
+            let rest = path.strip_prefix(old).ok()?;
+            Some(if rest.as_os_str().is_empty() {
+                new.clone()
+            } else {
+                new.join(rest)
+            })
+        })
+    }
+
+    /// Rewrite the paths in the text of a wine `.reg` file, returning the new text and how many were replaced.
+    /// Wine stores unix paths both as they are and behind the `Z:` drive with escaped backslashes.
+    pub fn rewrite_registry(&self, text: &str) -> (String, usize) {
+        let windows = |p: &Path| p.to_string_lossy().replace('/', "\\\\");
+        let mut replacements = Vec::new();
+        for (old, new) in &self.paths {
+            replacements.push((
+                old.to_string_lossy().to_string(),
+                new.to_string_lossy().to_string(),
+            ));
+            replacements.push((windows(old), windows(new)));
+        }
+
+        // A single pass, so a replaced path is never rewritten again by a shorter one
+        let mut out = String::with_capacity(text.len());
+        let mut count = 0;
+        let mut rest = text;
+        'outer: while let Some(c) = rest.chars().next() {
+            for (old, new) in &replacements {
+                if let Some(after) = rest.strip_prefix(old.as_str()) {
+                    let at_boundary = after.chars().next().is_none_or(|c| {
+                        matches!(c, '/' | '\\' | '"' | '\n' | '\r' | ';' | ',' | ':')
+                    });
+                    if at_boundary {
+                        out.push_str(new);
+                        rest = after;
+                        count += 1;
+                        continue 'outer;
+                    }
+                }
+            }
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        (out, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_paths_in_the_registry() {
+        let mut rewriter = PathRewriter::new();
+        rewriter.add(Path::new("/home/alice"), Path::new("/home/bob"));
+        rewriter.add(
+            Path::new("/home/alice/.local/share/proton-launch/compat/hl2"),
+            Path::new("/data/compat/hl2"),
+        );
+        // Relative and unchanged paths can't be rewritten
+        rewriter.add(Path::new("games"), Path::new("/games"));
+        rewriter.add(Path::new("/games"), Path::new("/games"));
+
+        let text = r#"[Software\\Valve\\Half-Life 2] 1681234567
+"Install"="/home/alice/Games/hl2"
+"Drive"="Z:\\home\\alice\\Games\\hl2"
+"Prefix"="/home/alice/.local/share/proton-launch/compat/hl2/pfx"
+"Home"="/home/alice"
+"Other"="/home/alice2/Games"
+"Escaped"="Z:\\home\\alicex"
+"#;
+        let (rewritten, count) = rewriter.rewrite_registry(text);
+        assert_eq!(
+            rewritten,
+            r#"[Software\\Valve\\Half-Life 2] 1681234567
+"Install"="/home/bob/Games/hl2"
+"Drive"="Z:\\home\\bob\\Games\\hl2"
+"Prefix"="/data/compat/hl2/pfx"
+"Home"="/home/bob"
+"Other"="/home/alice2/Games"
+"Escaped"="Z:\\home\\alicex"
+"#
+        );
+        assert_eq!(count, 4);
+        assert_eq!(
+            rewriter.rewrite_path(Path::new("/home/alice/Games")),
+            Some(PathBuf::from("/home/bob/Games"))
+        );
+        assert_eq!(rewriter.rewrite_path(Path::new("/home/alice2")), None);
+    }
+
+    #[test]
+    fn writes_zeros_as_holes() {
+        let mut data = vec![b'a'; 64 * 1024];
+        data.extend(vec![0; 256 * 1024]);
+        data.extend(b"end");
+        let size = data.len() as u64 + 64 * 1024;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sparse");
+        write_sparse(data.as_slice(), &path, size).unwrap();
+
+        let written = std::fs::read(&path).unwrap();
+        assert_eq!(written.len() as u64, size);
+        assert_eq!(&written[..data.len()], data.as_slice());
+        assert!(written[data.len()..].iter().all(|b| *b == 0));
+        assert!(is_sparse(&path.metadata().unwrap()));
+    }
+}
@@ -1,10 +1,30 @@
-use std::{fmt::Display, path::PathBuf};
-
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
+use serde::{Deserialize, Serialize};
 
 use crate::steam::SteamData;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// The folder containing `wine` and `wineserver` in a proton install.
+/// Newer versions keep them in `files`, older ones in `dist`
+pub fn wine_bin_dir(proton_path: &Path) -> PathBuf {
+    let files = proton_path.join("files/bin");
+    if files.is_dir() {
+        files
+    } else {
+        proton_path.join("dist/bin")
+    }
+}
+
+/// The proton version a prefix was last used with, as proton writes it into the compat dir
+pub fn prefix_version(compat_dir: &Path) -> Option<String> {
+    let version = std::fs::read_to_string(compat_dir.join("version")).ok()?;
+    Some(version.trim().to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ProtonVersion {
     Proton37Beta,
     Proton37,
@@ -0,0 +1,687 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write as _},
+    path::Path,
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("Could not read the registry file: {}", .0)]
+    IOError(#[from] std::io::Error),
+
+    #[error("Could not parse line {} of the registry file: {}", .line, .message)]
+    Parse { line: usize, message: String },
+}
+
+type RegistryResult<T> = Result<T, RegistryError>;
+
+/// The registry hives wine keeps in the prefix, with the root key they hold
+pub const HIVES: [(&str, &str); 2] = [
+    ("user.reg", "HKEY_CURRENT_USER"),
+    ("system.reg", "HKEY_LOCAL_MACHINE"),
+];
+
+/// Every hive file wine keeps in the prefix, `userdef.reg` holds the defaults for new users
+pub const HIVE_FILES: [&str; 3] = ["user.reg", "system.reg", "userdef.reg"];
+
+/// A registry file the way wine stores its hives, like `user.reg` in the prefix.
+///
+/// The format looks like this, with key paths relative to the root of the hive:
+/// ```text
+/// WINE REGISTRY Version 2
+/// ;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+///
+/// [Software\\Vendor\\Game] 1681234567
+/// #time=1d96c5e5a8b1c2e
+/// "Name"="Value"
+/// "Count"=dword:00000002
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegistryFile {
+    /// The lines before the first key, like the version and `#arch`
+    pub header: Vec<String>,
+    /// Keys in the order they are in the file
+    pub keys: Vec<RegKey>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegKey {
+    /// Path relative to the root of the hive, with single backslashes like `Software\Vendor\Game`
+    pub path: String,
+    /// When the key was last written, in seconds since the unix epoch as wine wrote it
+    pub modified: Option<String>,
+    /// Lines like `#time=...` or `#class="..."` that wine keeps about the key
+    pub meta: Vec<String>,
+    pub values: Vec<RegValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegValue {
+    /// `None` for the default value, written as `@`
+    pub name: Option<String>,
+    /// The data as written in the file, like `"text"`, `dword:00000001` or `hex:01,02`.
+    /// Data that was continued over several lines is joined into one.
+    pub data: String,
+}
+
+/// The data of a value, decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegData {
+    String(String),
+    Dword(u32),
+    /// Data of the given registry type as text, like `str(2):` for `REG_EXPAND_SZ`
+    TypedString {
+        kind: u32,
+        value: String,
+    },
+    /// Raw bytes, `kind` is the registry type, 3 for plain `hex:`
+    Binary {
+        kind: u32,
+        bytes: Vec<u8>,
+    },
+}
+
+/// How a key differs between two registry files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyDiff {
+    Added(RegKey),
+    Removed(RegKey),
+    Changed {
+        path: String,
+        values: Vec<ValueDiff>,
+    },
+}
+
+/// A value that was added, removed or changed, `old` or `new` is `None` if it didn't exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueDiff {
+    pub name: Option<String>,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl RegistryFile {
+    pub fn load(path: &Path) -> RegistryResult<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> RegistryResult<()> {
+        Ok(std::fs::write(path, self.to_string())?)
+    }
+
+    pub fn parse(text: &str) -> RegistryResult<Self> {
+        let mut file = Self::default();
+        let mut lines = text.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let error = |message: &str| RegistryError::Parse {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            if let Some(rest) = line.strip_prefix('[') {
+                let (path, rest) = unescape(rest, ']').ok_or_else(|| error("unclosed key"))?;
+                let modified = rest.trim();
+                file.keys.push(RegKey {
+                    path,
+                    modified: (!modified.is_empty()).then(|| modified.to_string()),
+                    ..Default::default()
+                });
+                continue;
+            }
+            let Some(key) = file.keys.last_mut() else {
+                file.header.push(line.to_string());
+                continue;
+            };
+            if line.starts_with('#') {
+                key.meta.push(line.to_string());
+            } else if line.starts_with('"') || line.starts_with('@') {
+                let (name, rest) = match line.strip_prefix('"') {
+                    Some(rest) => {
+                        let (name, rest) =
+                            unescape(rest, '"').ok_or_else(|| error("unclosed value name"))?;
+                        (Some(name), rest)
+                    }
+                    None => (None, &line[1..]),
+                };
+                let mut data = rest
+                    .strip_prefix('=')
+                    .ok_or_else(|| error("value without data"))?
+                    .to_string();
+                // Long binary data is continued on the next line after a backslash
+                while data.ends_with('\\') && !data.starts_with('"') {
+                    data.pop();
+                    match lines.next() {
+                        Some((_, next)) => data.push_str(next.trim_start()),
+                        None => return Err(error("data continues past the end of the file")),
+                    }
+                }
+                key.values.push(RegValue { name, data });
+            } else if !line.trim().is_empty() && !line.starts_with(';') {
+                return Err(error("expected a key, a value or a comment"));
+            }
+        }
+        // The empty line before the first key is written again with the key
+        while file.header.last().is_some_and(|l| l.trim().is_empty()) {
+            file.header.pop();
+        }
+        Ok(file)
+    }
+
+    /// Find a key by its path, ignoring case like Windows does
+    pub fn get(&self, path: &str) -> Option<&RegKey> {
+        let path = normalize(path);
+        self.keys.iter().find(|k| normalize(&k.path) == path)
+    }
+
+    /// A file with the same header that only has the given keys and the keys below them
+    pub fn extract<S: AsRef<str>>(&self, paths: &[S]) -> Self {
+        let paths: Vec<String> = paths.iter().map(|p| normalize(p.as_ref())).collect();
+        Self {
+            header: self.header.clone(),
+            keys: self
+                .keys
+                .iter()
+                .filter(|k| paths.iter().any(|p| is_below(&k.path, p)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Replace a key and everything below it with what `from` has there.
+    /// If `from` doesn't have the key, it is removed.
+    pub fn replace_subtree(&mut self, path: &str, from: &RegistryFile) {
+        let path = normalize(path);
+        self.keys.retain(|k| !is_below(&k.path, &path));
+        for key in from.keys.iter().filter(|k| is_below(&k.path, &path)) {
+            // Wine writes the keys in tree order, new ones go where it would put them
+            let order = sort_key(&key.path);
+            let at = self
+                .keys
+                .iter()
+                .position(|k| sort_key(&k.path) > order)
+                .unwrap_or(self.keys.len());
+            self.keys.insert(at, key.clone());
+        }
+    }
+
+    /// What changed from `old` to `new`, in tree order.
+    /// Only values count, keys that were just written again are not changed.
+    pub fn diff(old: &RegistryFile, new: &RegistryFile) -> Vec<KeyDiff> {
+        let by_path = |file: &RegistryFile| -> BTreeMap<Vec<String>, RegKey> {
+            file.keys
+                .iter()
+                .map(|k| (sort_key(&k.path), k.clone()))
+                .collect()
+        };
+        let old_keys = by_path(old);
+        let mut new_keys = by_path(new);
+        let mut diffs = BTreeMap::new();
+        for (order, old_key) in old_keys {
+            match new_keys.remove(&order) {
+                None => {
+                    diffs.insert(order, KeyDiff::Removed(old_key));
+                }
+                Some(new_key) => {
+                    let values = old_key.diff_values(&new_key);
+                    if !values.is_empty() {
+                        diffs.insert(
+                            order,
+                            KeyDiff::Changed {
+                                path: new_key.path,
+                                values,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        for (order, new_key) in new_keys {
+            diffs.insert(order, KeyDiff::Added(new_key));
+        }
+        diffs.into_values().collect()
+    }
+}
+
+impl Display for RegistryFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.header {
+            writeln!(f, "{}", line)?;
+        }
+        for key in &self.keys {
+            writeln!(f)?;
+            write!(f, "[{}]", escape(&key.path, &['[', ']']))?;
+            if let Some(modified) = &key.modified {
+                write!(f, " {}", modified)?;
+            }
+            writeln!(f)?;
+            for meta in &key.meta {
+                writeln!(f, "{}", meta)?;
+            }
+            for value in &key.values {
+                writeln!(f, "{}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RegKey {
+    pub fn value(&self, name: Option<&str>) -> Option<&RegValue> {
+        self.values.iter().find(|v| same_name(&v.name, name))
+    }
+
+    fn diff_values(&self, new: &RegKey) -> Vec<ValueDiff> {
+        let mut diffs = Vec::new();
+        for old in &self.values {
+            let new = new.value(old.name.as_deref());
+            if new.map(|v| &v.data) != Some(&old.data) {
+                diffs.push(ValueDiff {
+                    name: old.name.clone(),
+                    old: Some(old.data.clone()),
+                    new: new.map(|v| v.data.clone()),
+                });
+            }
+        }
+        for value in &new.values {
+            if self.value(value.name.as_deref()).is_none() {
+                diffs.push(ValueDiff {
+                    name: value.name.clone(),
+                    old: None,
+                    new: Some(value.data.clone()),
+                });
+            }
+        }
+        diffs
+    }
+}
+
+impl RegValue {
+    /// Decode the data, `None` if it is in a form this doesn't know
+    pub fn parse_data(&self) -> Option<RegData> {
+        let data = self.data.as_str();
+        if let Some(rest) = data.strip_prefix('"') {
+            return Some(RegData::String(unescape(rest, '"')?.0));
+        }
+        if let Some(hex) = data.strip_prefix("dword:") {
+            return u32::from_str_radix(hex, 16).ok().map(RegData::Dword);
+        }
+        if let Some(rest) = data.strip_prefix("str(") {
+            let (kind, rest) = rest.split_once("):\"")?;
+            return Some(RegData::TypedString {
+                kind: u32::from_str_radix(kind, 16).ok()?,
+                value: unescape(rest, '"')?.0,
+            });
+        }
+        let (kind, bytes) = match data.strip_prefix("hex:") {
+            Some(bytes) => (3, bytes),
+            None => {
+                let (kind, bytes) = data.strip_prefix("hex(")?.split_once("):")?;
+                (u32::from_str_radix(kind, 16).ok()?, bytes)
+            }
+        };
+        let bytes = bytes
+            .split(',')
+            .filter(|b| !b.trim().is_empty())
+            .map(|b| u8::from_str_radix(b.trim(), 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(RegData::Binary { kind, bytes })
+    }
+}
+
+impl Display for RegValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "\"{}\"={}", escape(name, &['"']), self.data),
+            None => write!(f, "@={}", self.data),
+        }
+    }
+}
+
+/// Split a registry key like ludusavi and regedit write it, `HKEY_CURRENT_USER/Software/Vendor`
+/// or `HKCU\Software\Vendor`, into the hive file that holds it and the path inside that hive
+pub fn hive_of(key: &str) -> Option<(&'static str, String)> {
+    let key = key.replace('/', "\\");
+    let (root, path) = key.split_once('\\').unwrap_or((&key, ""));
+    let root = match root.to_uppercase().as_str() {
+        "HKCU" => "HKEY_CURRENT_USER",
+        "HKLM" => "HKEY_LOCAL_MACHINE",
+        _ => root,
+    };
+    HIVES
+        .iter()
+        .find(|(_, hive_root)| hive_root.eq_ignore_ascii_case(root))
+        .map(|(hive, _)| (*hive, path.trim_matches('\\').to_string()))
+}
+
+/// Whether `path` is the key `parent` or below it, `parent` has to be normalized
+fn is_below(path: &str, parent: &str) -> bool {
+    let path = normalize(path);
+    parent.is_empty()
+        || path == parent
+        || path
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.starts_with('\\'))
+}
+
+fn normalize(path: &str) -> String {
+    path.trim_matches('\\').to_lowercase()
+}
+
+fn sort_key(path: &str) -> Vec<String> {
+    normalize(path).split('\\').map(str::to_string).collect()
+}
+
+fn same_name(a: &Option<String>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Read an escaped string up to the unescaped `end`, returning it and what comes after `end`.
+/// Escapes are read like wine does: `\x` escapes are UTF-16 code units, so a surrogate pair becomes one character,
+/// and a surrogate without its pair can't be in a string so it is kept as the escape it was written as.
+fn unescape(s: &str, end: char) -> Option<(String, &str)> {
+    let mut out = String::new();
+    let mut rest = s;
+    loop {
+        let c = rest.chars().next()?;
+        rest = &rest[c.len_utf8()..];
+        if c == end {
+            return Some((out, rest));
+        }
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let escape = rest;
+        let escaped = rest.chars().next()?;
+        rest = &rest[escaped.len_utf8()..];
+        match escaped {
+            'a' => out.push('\x07'),
+            'b' => out.push('\x08'),
+            'e' => out.push('\x1b'),
+            'f' => out.push('\x0c'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\x0b'),
+            '0'..='7' => {
+                let len = 1 + rest
+                    .bytes()
+                    .take(2)
+                    .take_while(|b| (b'0'..=b'7').contains(b))
+                    .count();
+                let code = u32::from_str_radix(&escape[..len], 8).ok()?;
+                out.push(char::from_u32(code)?);
+                rest = &escape[len..];
+            }
+            'x' => {
+                let Some((unit, after)) = hex_unit(rest) else {
+                    out.push('x');
+                    continue;
+                };
+                let low = after.strip_prefix("\\x").and_then(hex_unit);
+                match (char::from_u32(unit), low) {
+                    (Some(c), _) => {
+                        out.push(c);
+                        rest = after;
+                    }
+                    (None, Some((low, after_low)))
+                        if (0xd800..0xdc00).contains(&unit) && (0xdc00..0xe000).contains(&low) =>
+                    {
+                        out.push(char::from_u32(
+                            0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00),
+                        )?);
+                        rest = after_low;
+                    }
+                    (None, _) => {
+                        out.push('\\');
+                        out.push_str(&escape[..escape.len() - after.len()]);
+                        rest = after;
+                    }
+                }
+            }
+            other => out.push(other),
+        }
+    }
+}
+
+/// The code unit of a `\x` escape, up to 4 hex digits, and what comes after it
+fn hex_unit(s: &str) -> Option<(u32, &str)> {
+    let len = s.bytes().take(4).take_while(u8::is_ascii_hexdigit).count();
+    let unit = u32::from_str_radix(&s[..len], 16).ok()?;
+    Some((unit, &s[len..]))
+}
+
+/// The letters of the C escapes wine uses for control characters, `.` for those it writes in octal
+const CONTROL_ESCAPES: &[u8; 32] = b".......abtnvfr.............e....";
+
+/// Escape a string the same way wine's `dump_strW` does, so hives are written back like wine writes them.
+/// `delimiters` get a backslash too, `[` and `]` in key paths and `"` in value names.
+fn escape(s: &str, delimiters: &[char]) -> String {
+    let units: Vec<u16> = s.encode_utf16().collect();
+    let mut out = String::new();
+    for (i, &unit) in units.iter().enumerate() {
+        let next = units.get(i + 1).copied().unwrap_or_default();
+        if unit > 127 {
+            // A hex digit right after the escape would be read as part of it
+            if next < 128 && (next as u8).is_ascii_hexdigit() {
+                let _ = write!(out, "\\x{:04x}", unit);
+            } else {
+                let _ = write!(out, "\\x{:x}", unit);
+            }
+        } else if unit < 32 {
+            match CONTROL_ESCAPES[unit as usize] {
+                b'.' if (b'0' as u16..=b'7' as u16).contains(&next) => {
+                    let _ = write!(out, "\\{:03o}", unit);
+                }
+                b'.' => {
+                    let _ = write!(out, "\\{:o}", unit);
+                }
+                letter => {
+                    out.push('\\');
+                    out.push(letter as char);
+                }
+            }
+        } else {
+            let c = unit as u8 as char;
+            if c == '\\' || delimiters.contains(&c) {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HIVE: &str = r#"WINE REGISTRY Version 2
+;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+
+#arch=win64
+
+[Software\\Vendor\\Game] 1681234567
+#time=1d96c5e5a8b1c2e
+@="default"
+"Volume"=dword:00000005
+"Name"="Player \"One\""
+
+[Software\\Vendor\\Game\\Video] 1681234567
+"Blob"=hex:01,02,03,\
+  04,05
+
+[Software\\Wine] 1681234567
+"Version"="win10"
+"#;
+
+    #[test]
+    fn parses_keys_and_values() {
+        let file = RegistryFile::parse(HIVE).unwrap();
+        assert_eq!(file.header.len(), 4);
+        assert_eq!(file.keys.len(), 3);
+        let game = file.get("software\\vendor\\GAME").unwrap();
+        assert_eq!(game.path, "Software\\Vendor\\Game");
+        assert_eq!(game.modified.as_deref(), Some("1681234567"));
+        assert_eq!(game.meta, ["#time=1d96c5e5a8b1c2e"]);
+        assert_eq!(
+            game.value(None).unwrap().parse_data(),
+            Some(RegData::String("default".to_string()))
+        );
+        assert_eq!(
+            game.value(Some("volume")).unwrap().parse_data(),
+            Some(RegData::Dword(5))
+        );
+        assert_eq!(
+            game.value(Some("Name")).unwrap().parse_data(),
+            Some(RegData::String("Player \"One\"".to_string()))
+        );
+        let blob = file.get("Software\\Vendor\\Game\\Video").unwrap();
+        assert_eq!(
+            blob.value(Some("Blob")).unwrap().parse_data(),
+            Some(RegData::Binary {
+                kind: 3,
+                bytes: vec![1, 2, 3, 4, 5]
+            })
+        );
+    }
+
+    #[test]
+    fn writes_back_what_it_read() {
+        let file = RegistryFile::parse(HIVE).unwrap();
+        let written = file.to_string();
+        assert_eq!(RegistryFile::parse(&written).unwrap(), file);
+        // Only the continued binary data is joined into one line
+        assert_eq!(written, HIVE.replace("03,\\\n  04", "03,04"));
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        for text in [
+            "[Software\\\\Game\n",
+            "[Software] 1\n\"Name\n",
+            "[Software] 1\n\"Name\"\n",
+            "[Software] 1\n\"Blob\"=hex:01,\\\n",
+            "[Software] 1\nnonsense\n",
+        ] {
+            assert!(RegistryFile::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn escapes_like_wine() {
+        let key = RegKey {
+            path: "Software\\[Brackets]\\Caf\u{e9}1\u{e9}\u{1f600}".to_string(),
+            values: vec![RegValue {
+                name: Some("Tab\tNul\0\u{1}7\"".to_string()),
+                data: "dword:00000001".to_string(),
+            }],
+            ..Default::default()
+        };
+        let file = RegistryFile {
+            header: Vec::new(),
+            keys: vec![key],
+        };
+        let written = file.to_string();
+        assert_eq!(
+            written,
+            "\n[Software\\\\\\[Brackets\\]\\\\Caf\\x00e91\\xe9\\xd83d\\xde00]\n\"Tab\\tNul\\0\\0017\\\"\"=dword:00000001\n"
+        );
+        assert_eq!(RegistryFile::parse(&written).unwrap(), file);
+    }
+
+    #[test]
+    fn unescapes_like_wine() {
+        fn unescaped(s: &str) -> (String, &str) {
+            unescape(s, '"').unwrap()
+        }
+        assert_eq!(
+            unescaped("\\xd83d\\xde00\" rest"),
+            ("\u{1f600}".to_string(), " rest")
+        );
+        assert_eq!(unescaped("\\xe9\\x41\""), ("\u{e9}A".to_string(), ""));
+        assert_eq!(unescaped("\\x20ac5\""), ("\u{20ac}5".to_string(), ""));
+        assert_eq!(unescaped("\\101\\0\\e\\q\""), ("A\0\x1bq".to_string(), ""));
+        assert_eq!(unescaped("\\xg\""), ("xg".to_string(), ""));
+        // A surrogate without its pair stays the escape it was
+        assert_eq!(unescaped("\\xd83dx\""), ("\\xd83dx".to_string(), ""));
+        assert_eq!(unescape("no end", '"'), None);
+        assert_eq!(unescape("trailing \\", '"'), None);
+    }
+
+    #[test]
+    fn extracts_and_replaces_subtrees() {
+        let file = RegistryFile::parse(HIVE).unwrap();
+        let game = file.extract(&["Software\\Vendor\\Game"]);
+        assert_eq!(game.header, file.header);
+        assert_eq!(game.keys.len(), 2);
+        assert!(file.extract(&["Software\\Vendor\\Gam"]).keys.is_empty());
+
+        let mut changed = RegistryFile::parse(HIVE).unwrap();
+        changed
+            .keys
+            .retain(|k| k.path != "Software\\Vendor\\Game\\Video");
+        changed.keys[0].values.clear();
+        changed.replace_subtree("software\\vendor\\game", &game);
+        assert_eq!(changed, file);
+
+        changed.replace_subtree("Software\\Vendor", &RegistryFile::default());
+        assert_eq!(changed.keys.len(), 1);
+        assert_eq!(changed.keys[0].path, "Software\\Wine");
+    }
+
+    #[test]
+    fn diffs_values() {
+        let old = RegistryFile::parse(HIVE).unwrap();
+        let mut new = old.clone();
+        assert!(RegistryFile::diff(&old, &new).is_empty());
+
+        new.keys[0].modified = Some("1700000000".to_string());
+        new.keys[0].values[1].data = "dword:00000006".to_string();
+        new.keys[0].values.push(RegValue {
+            name: Some("New".to_string()),
+            data: "\"yes\"".to_string(),
+        });
+        new.keys.remove(1);
+        let diffs = RegistryFile::diff(&old, &new);
+        assert_eq!(
+            diffs,
+            [
+                KeyDiff::Changed {
+                    path: "Software\\Vendor\\Game".to_string(),
+                    values: vec![
+                        ValueDiff {
+                            name: Some("Volume".to_string()),
+                            old: Some("dword:00000005".to_string()),
+                            new: Some("dword:00000006".to_string()),
+                        },
+                        ValueDiff {
+                            name: Some("New".to_string()),
+                            old: None,
+                            new: Some("\"yes\"".to_string()),
+                        },
+                    ],
+                },
+                KeyDiff::Removed(old.keys[1].clone()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_the_hive_of_a_key() {
+        assert_eq!(
+            hive_of("HKEY_CURRENT_USER/Software/Vendor"),
+            Some(("user.reg", "Software\\Vendor".to_string()))
+        );
+        assert_eq!(
+            hive_of("hklm\\Software\\"),
+            Some(("system.reg", "Software".to_string()))
+        );
+        assert_eq!(hive_of("HKEY_CLASSES_ROOT\\.txt"), None);
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions, TryLockError},
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    backups::BackupManifest,
+    hash::{hash_reader, is_hash, to_hex},
+};
+
+/// Deduplicated storage for backups, in a folder of the data dir.
+///
+/// The contents of every file are stored once as a zstd compressed blob named after their sha256,
+/// and a backup is a snapshot: its manifest, stored as `<id>.snapshot` next to the other backups of the game.
+/// Saves that didn't change between backups only take up space once.
+/// Removing snapshots leaves their blobs behind until [`Repository::prune`] removes the ones nothing uses.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    dir: PathBuf,
+}
+
+/// Extension of the snapshots of a repository
+pub const SNAPSHOT_EXTENSION: &str = "snapshot";
+
+/// How old a temporary blob has to be before prune counts it as left behind by an interrupted backup
+const TMP_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Lock on a repository, released when dropped
+#[derive(Debug)]
+pub struct RepositoryLock {
+    _file: File,
+}
+
+/// What is wrong with a repository
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub blobs: usize,
+    /// Blobs whose contents don't match their hash
+    pub damaged: Vec<String>,
+    /// Blobs snapshots need that aren't there, with the snapshot and the file they are for
+    pub missing: Vec<(PathBuf, PathBuf, String)>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.damaged.is_empty() && self.missing.is_empty()
+    }
+}
+
+impl Repository {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn is_snapshot(path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == SNAPSHOT_EXTENSION)
+    }
+
+    /// Lock the repository, so a prune can't remove the blobs of a backup that is still being made.
+    /// Waits for whoever has it, neither backups nor prunes hold it for long.
+    pub fn lock(&self) -> std::io::Result<RepositoryLock> {
+        std::fs::create_dir_all(&self.dir)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.dir.join("lock"))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                println!("Waiting for another backup or prune of the repository to finish...");
+                file.lock()?;
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+        Ok(RepositoryLock { _file: file })
+    }
+
+    fn blobs_dir(&self) -> PathBuf {
+        self.dir.join("blobs")
+    }
+
+    pub fn blob_path(&self, hash: &str) -> PathBuf {
+        self.blobs_dir()
+            .join(hash.get(..2).unwrap_or_default())
+            .join(hash)
+    }
+
+    pub fn has_blob(&self, hash: &str) -> bool {
+        self.blob_path(hash).is_file()
+    }
+
+    /// Store the contents of a file, returning their hash.
+    /// The hash is taken while storing, so it is right even if the file changed since it was last hashed.
+    pub fn add_blob(&self, source: &Path) -> std::io::Result<String> {
+        self.add_blob_from(File::open(source)?)
+    }
+
+    /// Store whatever `r` reads, returning the hash of it
+    pub fn add_blob_from(&self, mut r: impl Read) -> std::io::Result<String> {
+        std::fs::create_dir_all(self.blobs_dir())?;
+        let tmp = self.blobs_dir().join(format!("tmp-{}", std::process::id()));
+        let mut hasher = Sha256::new();
+        {
+            let mut encoder = zstd::Encoder::new(File::create(&tmp)?, 3)?;
+            let mut buf = vec![0u8; 64 * 1024];
+            loop {
+                let n = r.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+                encoder.write_all(&buf[..n])?;
+            }
+            encoder.finish()?.sync_all()?;
+        }
+        let hash = to_hex(&hasher.finalize());
+        let path = self.blob_path(&hash);
+        if path.is_file() {
+            std::fs::remove_file(&tmp)?;
+        } else {
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::rename(&tmp, &path)?;
+        }
+        Ok(hash)
+    }
+
+    /// The contents of a blob
+    pub fn open_blob(
+        &self,
+        hash: &str,
+    ) -> std::io::Result<zstd::Decoder<'static, BufReader<File>>> {
+        let f = File::open(self.blob_path(hash)).map_err(|e| {
+            std::io::Error::new(
+                e.kind(),
+                format!("blob {} is missing from the backup repository", hash),
+            )
+        })?;
+        zstd::Decoder::new(f)
+    }
+
+    /// Hashes of all blobs in the repository, with their paths
+    pub fn blobs(&self) -> std::io::Result<Vec<(String, PathBuf)>> {
+        let mut blobs = Vec::new();
+        if !self.blobs_dir().is_dir() {
+            return Ok(blobs);
+        }
+        for entry in walkdir::WalkDir::new(self.blobs_dir())
+            .min_depth(1)
+            .max_depth(2)
+        {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let hash = entry.file_name().to_string_lossy().to_string();
+                blobs.push((hash, entry.into_path()));
+            }
+        }
+        blobs.sort();
+        Ok(blobs)
+    }
+
+    /// Whether the contents of a blob still match its hash
+    pub fn verify_blob(&self, hash: &str) -> std::io::Result<bool> {
+        Ok(hash_reader(self.open_blob(hash)?)? == hash)
+    }
+
+    /// Remove the blobs none of the snapshots use, and whatever an interrupted backup left behind.
+    /// Returns the removed blobs with their size, nothing is removed if `dry_run` is set.
+    /// The repository should be locked while the snapshots are listed and pruned.
+    pub fn prune(
+        &self,
+        snapshots: &[PathBuf],
+        dry_run: bool,
+    ) -> std::io::Result<Vec<(String, u64)>> {
+        let mut used = BTreeSet::new();
+        for snapshot in snapshots {
+            used.extend(read_snapshot(snapshot)?.files.into_values().map(|f| f.hash));
+        }
+        let mut removed = Vec::new();
+        for (hash, path) in self.blobs()? {
+            if used.contains(&hash) {
+                continue;
+            }
+            let metadata = path.metadata()?;
+            let age = metadata.modified()?.elapsed().unwrap_or_default();
+            if hash.starts_with("tmp-") && age < TMP_GRACE_PERIOD {
+                continue;
+            }
+            let size = metadata.len();
+            if !dry_run {
+                std::fs::remove_file(&path)?;
+            }
+            removed.push((hash, size));
+        }
+        Ok(removed)
+    }
+
+    /// Verify every blob, and that every blob the snapshots need is there
+    pub fn check(&self, snapshots: &[PathBuf]) -> std::io::Result<CheckReport> {
+        let mut report = CheckReport::default();
+        for (hash, _) in self.blobs()? {
+            if hash.starts_with("tmp-") {
+                continue;
+            }
+            report.blobs += 1;
+            if !self.verify_blob(&hash).unwrap_or(false) {
+                report.damaged.push(hash);
+            }
+        }
+        for snapshot in snapshots {
+            for (path, file) in read_snapshot(snapshot)?.files {
+                if !self.has_blob(&file.hash) {
+                    report.missing.push((snapshot.clone(), path, file.hash));
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// A snapshot is just the manifest of the backup.
+/// Its hashes become paths in the repository, so anything that isn't a sha256 is refused.
+pub fn read_snapshot(path: &Path) -> std::io::Result<BackupManifest> {
+    let f = File::open(path)?;
+    let manifest: BackupManifest = serde_json::from_reader(f)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if let Some((file, info)) = manifest.files.iter().find(|(_, f)| !is_hash(&f.hash)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "{} has an invalid hash for {}: {:?}",
+                path.display(),
+                file.display(),
+                info.hash
+            ),
+        ));
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::backups::ManifestFile;
+
+    fn write_snapshot(repository: &Repository, name: &str, hashes: &[&str]) -> PathBuf {
+        let mut manifest = BackupManifest::new("game", OffsetDateTime::now_utc());
+        for (i, hash) in hashes.iter().enumerate() {
+            manifest.files.insert(
+                PathBuf::from(format!("pfx/{}.sav", i)),
+                ManifestFile {
+                    size: 0,
+                    hash: hash.to_string(),
+                },
+            );
+        }
+        let path = repository.dir.join(name);
+        std::fs::write(&path, manifest.to_json()).unwrap();
+        path
+    }
+
+    #[test]
+    fn blobs_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = &Repository::new(dir.path().to_path_buf());
+        let hash = repository.add_blob_from(&b"save data"[..]).unwrap();
+        assert!(is_hash(&hash));
+        assert_eq!(repository.add_blob_from(&b"save data"[..]).unwrap(), hash);
+        assert!(repository.has_blob(&hash));
+        assert!(repository.verify_blob(&hash).unwrap());
+        let mut content = String::new();
+        repository
+            .open_blob(&hash)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "save data");
+        assert_eq!(repository.blobs().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_snapshots_with_invalid_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = &Repository::new(dir.path().to_path_buf());
+        let valid = "0".repeat(64);
+        let ok = write_snapshot(repository, "ok.snapshot", &[&valid]);
+        assert!(read_snapshot(&ok).is_ok());
+        for hash in [
+            "../../../etc/passwd".to_string(),
+            "é".repeat(32),
+            "A".repeat(64),
+            "0".repeat(63),
+            String::new(),
+        ] {
+            let path = write_snapshot(repository, "bad.snapshot", &[&valid, &hash]);
+            assert!(read_snapshot(&path).is_err(), "{:?}", hash);
+        }
+        // Blob paths are only made of valid hashes, but odd names in the folder don't panic either
+        repository.blob_path("é");
+        repository.blob_path("");
+    }
+
+    #[test]
+    fn prune_removes_unused_blobs_and_old_temporary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = &Repository::new(dir.path().to_path_buf());
+        let used = repository.add_blob_from(&b"used"[..]).unwrap();
+        let unused = repository.add_blob_from(&b"unused"[..]).unwrap();
+        let snapshots = [write_snapshot(repository, "1.snapshot", &[&used])];
+        let blobs_dir = repository.blobs_dir();
+        std::fs::write(blobs_dir.join("tmp-1"), "").unwrap();
+        let old = File::create(blobs_dir.join("tmp-2")).unwrap();
+        old.set_modified(std::time::SystemTime::now() - 2 * TMP_GRACE_PERIOD)
+            .unwrap();
+
+        let removed = repository.prune(&snapshots, true).unwrap();
+        let mut removed: Vec<String> = removed.into_iter().map(|(hash, _)| hash).collect();
+        removed.sort();
+        let mut expected = vec![unused.clone(), "tmp-2".to_string()];
+        expected.sort();
+        assert_eq!(removed, expected);
+        assert!(repository.has_blob(&unused));
+
+        repository.prune(&snapshots, false).unwrap();
+        assert!(repository.has_blob(&used));
+        assert!(!repository.has_blob(&unused));
+        assert!(blobs_dir.join("tmp-1").is_file());
+        assert!(!blobs_dir.join("tmp-2").exists());
+        assert!(repository.check(&snapshots).unwrap().is_ok());
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::proton::wine_bin_dir;
+
+/// A running game, recorded in the run dir of its save name so other commands can find it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Pid of the proton process, this is also the id of its process group
+    pub pid: u32,
+    /// Path to the proton install the game was launched with
+    pub proton_path: PathBuf,
+    /// The compat dir the game is running in
+    pub compat_dir: PathBuf,
+}
+
+impl Session {
+    const FILE_NAME: &'static str = "session.toml";
+
+    pub fn new(pid: u32, proton_path: PathBuf, compat_dir: PathBuf) -> Self {
+        Self {
+            pid,
+            proton_path,
+            compat_dir,
+        }
+    }
+
+    fn path(run_dir: &Path) -> PathBuf {
+        run_dir.join(Self::FILE_NAME)
+    }
+
+    /// Read the session from a run dir, returns `None` if there is no session
+    /// or if the process it points to is no longer alive
+    pub fn load(run_dir: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(Self::path(run_dir)).ok()?;
+        let session: Self = toml::from_str(&content).ok()?;
+        if session.is_running() {
+            Some(session)
+        } else {
+            Self::remove(run_dir);
+            None
+        }
+    }
+
+    pub fn save(&self, run_dir: &Path) -> std::io::Result<()> {
+        let content = toml::to_string(self).unwrap();
+        std::fs::write(Self::path(run_dir), content)
+    }
+
+    pub fn remove(run_dir: &Path) {
+        let _ = std::fs::remove_file(Self::path(run_dir));
+    }
+
+    pub fn is_running(&self) -> bool {
+        Path::new("/proc").join(self.pid.to_string()).exists()
+    }
+
+    /// Path to the wineserver shipped with the proton install
+    pub fn wineserver(&self) -> PathBuf {
+        wine_bin_dir(&self.proton_path).join("wineserver")
+    }
+
+    /// Ask the wineserver of the prefix to shut down, which closes all windows programs in it
+    pub fn ask_to_stop(&self) -> std::io::Result<std::process::ExitStatus> {
+        Command::new(self.wineserver())
+            .env("WINEPREFIX", self.compat_dir.join("pfx"))
+            .arg("-k")
+            .status()
+    }
+
+    /// Send a signal to the whole process group of the session
+    pub fn signal(&self, signal: &str) -> std::io::Result<std::process::ExitStatus> {
+        Command::new("kill")
+            .arg(format!("-{}", signal))
+            .arg("--")
+            .arg(format!("-{}", self.pid))
+            .status()
+    }
+
+    /// Wait until the session exits, returns `false` if it is still running after the timeout
+    pub fn wait_for_exit(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while self.is_running() {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        true
+    }
+}
@@ -14,4 +14,3 @@ pub struct Shortcut {
 pub struct Shortcuts {
     shortcuts: Vec<Shortcut>,
 }
-
@@ -83,6 +83,19 @@ impl SteamData {
     pub fn get_app_dir(&self, app_id: u64) -> Option<PathBuf> {
         self.library_folders.get_app_dir(app_id)
     }
+
+    /// The manifests of all installed apps
+    pub fn app_manifests(&self) -> Vec<AppManifest> {
+        self.library_folders.app_manifests()
+    }
+}
+
+/// The interesting parts of an `appmanifest_<id>.acf` file
+#[derive(Debug, Clone)]
+pub struct AppManifest {
+    pub app_id: u64,
+    pub name: String,
+    pub install_dir: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -99,8 +112,8 @@ impl LibraryFolders {
             .iter()
             .filter(|(key, values)| key.parse::<u32>().is_ok() && values.len() == 1)
             .filter_map(|(_, values)| {
-                let lfo = values.get(0)?.get_obj()?;
-                let library_folder_string = lfo.get("path")?.get(0)?.get_str()?.to_string();
+                let lfo = values.first()?.get_obj()?;
+                let library_folder_string = lfo.get("path")?.first()?.get_str()?.to_string();
                 let apps = lfo
                     .get("apps")?
                     .iter()
@@ -125,16 +138,14 @@ impl LibraryFolders {
 
     pub fn get_app_dir(&self, app_id: u64) -> Option<PathBuf> {
         let library = self.0.iter().find(|lf| lf.has_game(app_id))?;
-        let manifest_location = library.path.join(format!("appmanifest_{}.acf", app_id));
-        if manifest_location.is_file() {
-            let manifest = read_to_string(manifest_location).ok()?;
-            let vdf = Vdf::parse(&manifest).ok()?;
-            let obj = vdf.value.get_obj()?;
-            let install_dir = obj.get("installdir")?.get(0)?.get_str()?;
-            return Some(library.path.join("common").join(install_dir));
-        }
+        library.manifest(app_id).map(|m| m.install_dir)
+    }
 
-        None
+    pub fn app_manifests(&self) -> Vec<AppManifest> {
+        self.0
+            .iter()
+            .flat_map(|lf| lf.apps.iter().filter_map(|app_id| lf.manifest(*app_id)))
+            .collect()
     }
 }
 
@@ -148,4 +159,25 @@ impl LibraryFolder {
     fn has_game(&self, appid: u64) -> bool {
         self.apps.contains(&appid)
     }
+
+    fn manifest(&self, app_id: u64) -> Option<AppManifest> {
+        let manifest_location = self.path.join(format!("appmanifest_{}.acf", app_id));
+        if manifest_location.is_file() {
+            let manifest = read_to_string(manifest_location).ok()?;
+            let vdf = Vdf::parse(&manifest).ok()?;
+            let obj = vdf.value.get_obj()?;
+            let install_dir = obj.get("installdir")?.first()?.get_str()?;
+            let name = obj
+                .get("name")
+                .and_then(|n| n.first()?.get_str())
+                .unwrap_or(install_dir);
+            return Some(AppManifest {
+                app_id,
+                name: name.to_string(),
+                install_dir: self.path.join("common").join(install_dir),
+            });
+        }
+
+        None
+    }
 }
@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+/// Find the wine prefix a path is in, the prefix is the folder containing `drive_c`
+pub fn find_prefix(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|p| p.join("drive_c").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Find the compat dir a path is in, if it is in a proton prefix.
+/// Proton prefixes are always called `pfx`, inside the compat dir
+pub fn find_compat_dir(path: &Path) -> Option<PathBuf> {
+    let prefix = find_prefix(path)?;
+    if prefix.file_name()? != "pfx" {
+        return None;
+    }
+    prefix.parent().map(Path::to_path_buf)
+}
+
+/// Translate a Windows path like `C:\Games\game.exe` to the path it points to inside a prefix.
+/// Drives other than `C:` are looked up in `dosdevices`, which is where wine keeps its drive symlinks.
+pub fn unix_path(prefix: &Path, windows_path: &str) -> PathBuf {
+    let windows_path = windows_path.replace('\\', "/");
+    let (drive, rest) = match windows_path.split_once(':') {
+        Some((drive, rest)) if drive.len() == 1 => (drive.to_ascii_lowercase(), rest),
+        _ => return PathBuf::from(windows_path),
+    };
+    let mut path = if drive == "c" {
+        prefix.join("drive_c")
+    } else {
+        prefix.join("dosdevices").join(format!("{}:", drive))
+    };
+    path.extend(rest.split('/').filter(|p| !p.is_empty()));
+    path
+}
+
+/// Split a Windows command line into its arguments.
+/// This follows the basic rules, arguments are split on whitespace unless they are in double quotes
+pub fn split_command_line(command_line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for c in command_line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_windows_paths() {
+        let prefix = Path::new("/compat/pfx");
+        assert_eq!(
+            unix_path(prefix, "C:\\Program Files\\Game\\game.exe"),
+            Path::new("/compat/pfx/drive_c/Program Files/Game/game.exe")
+        );
+        assert_eq!(
+            unix_path(prefix, "d:/Games//game.exe"),
+            Path::new("/compat/pfx/dosdevices/d:/Games/game.exe")
+        );
+        assert_eq!(unix_path(prefix, "c:"), Path::new("/compat/pfx/drive_c"));
+        assert_eq!(
+            unix_path(prefix, "bin\\game.exe"),
+            Path::new("bin/game.exe")
+        );
+    }
+
+    #[test]
+    fn splits_command_lines() {
+        assert_eq!(
+            split_command_line("  -novid \"C:\\Program Files\\x\"  -w 1920 \"\" a\"b c\"d"),
+            ["-novid", "C:\\Program Files\\x", "-w", "1920", "", "ab cd"]
+        );
+        assert!(split_command_line(" \t ").is_empty());
+    }
+}